@@ -1,14 +1,18 @@
 //! Node.js-specific key management functions.
 //!
-//! This module provides functions for importing/exporting keys as JWK JSON strings,
-//! loading keys from environment variables, and signing messages with secp256k1 keys.
+//! This module provides functions for importing/exporting keys as JWK JSON strings
+//! (optionally encrypted with a passphrase), loading keys from environment variables,
+//! and signing messages with secp256k1 keys.
 
 #![cfg(feature = "nodejs")]
 
 use wasm_bindgen::prelude::*;
 
-use crate::session::TCWSessionManager;
-use tinycloud_sdk_rs::tinycloud_auth::ssi::jwk::JWK;
+use crate::session::{has_private_key, TCWSessionManager};
+use tinycloud_sdk_rs::tinycloud_auth::{
+    cacaos::siwe::eip55,
+    ssi::jwk::{Base64urlUInt, ECParams, Params, JWK},
+};
 
 /// Import a private key from a JWK JSON string.
 ///
@@ -46,6 +50,239 @@ pub fn export_key(manager: &TCWSessionManager, key_id: Option<String>) -> Result
         .ok_or_else(|| "Key not found".to_string())
 }
 
+/// Import every private key of a JWK Set (`{"keys": [...]}`).
+///
+/// Each key is imported under `{key_id_prefix}{kid}`, or `{key_id_prefix}{index}` when the
+/// key has no `kid`. Nothing is imported if any of the keys is public-only or, unless
+/// `override_existing` is set, if any of the key IDs is already in use.
+///
+/// # Arguments
+/// * `manager` - The session manager to import the keys into
+/// * `jwks_json` - JWK Set as a JSON string
+/// * `key_id_prefix` - Optional prefix for the key IDs (defaults to none)
+/// * `override_existing` - Whether to replace keys that already exist
+///
+/// # Returns
+/// The key IDs of the imported keys, in the order of the set
+#[wasm_bindgen(js_name = importJwks)]
+pub fn import_jwks(
+    manager: &mut TCWSessionManager,
+    jwks_json: String,
+    key_id_prefix: Option<String>,
+    override_existing: bool,
+) -> Result<Vec<String>, String> {
+    #[derive(serde::Deserialize)]
+    struct JwkSet {
+        keys: Vec<JWK>,
+    }
+
+    let jwks: JwkSet =
+        serde_json::from_str(&jwks_json).map_err(|e| format!("Invalid JWKS format: {}", e))?;
+    let prefix = key_id_prefix.unwrap_or_default();
+    let key_ids: Vec<String> = jwks
+        .keys
+        .iter()
+        .enumerate()
+        .map(|(index, jwk)| match &jwk.key_id {
+            Some(kid) => format!("{}{}", prefix, kid),
+            None => format!("{}{}", prefix, index),
+        })
+        .collect();
+
+    for (index, (jwk, key_id)) in jwks.keys.iter().zip(&key_ids).enumerate() {
+        if key_ids[..index].contains(key_id) {
+            return Err(format!("Duplicate key ID in JWKS: {}", key_id));
+        }
+        if !has_private_key(jwk) {
+            return Err(format!("session key {} has no private key", key_id));
+        }
+        if !override_existing && manager.has_key(key_id) {
+            return Err(format!("key already exists: {}", key_id));
+        }
+    }
+
+    for (jwk, key_id) in jwks.keys.into_iter().zip(&key_ids) {
+        manager.import_session_key_internal(jwk, Some(key_id.clone()), override_existing)?;
+    }
+    Ok(key_ids)
+}
+
+/// Import a raw secp256k1 private key, as used by most Ethereum tooling.
+///
+/// # Arguments
+/// * `manager` - The session manager to import the key into
+/// * `private_key_hex` - Hex-encoded 32-byte private key (with or without 0x prefix)
+/// * `key_id` - Optional key ID (defaults to "default")
+///
+/// # Returns
+/// The key ID of the imported key
+#[wasm_bindgen(js_name = importSecp256k1KeyHex)]
+pub fn import_secp256k1_key_hex(
+    manager: &mut TCWSessionManager,
+    private_key_hex: String,
+    key_id: Option<String>,
+) -> Result<String, String> {
+    use k256::elliptic_curve::sec1::ToEncodedPoint;
+
+    let signing_key = parse_secp256k1_key(&private_key_hex)?;
+    let public_key = k256::PublicKey::from(signing_key.verifying_key());
+    let public_point = ToEncodedPoint::to_encoded_point(&public_key, false);
+    let (x, y) = match (public_point.x(), public_point.y()) {
+        (Some(x), Some(y)) => (x.to_vec(), y.to_vec()),
+        _ => return Err("Failed to derive secp256k1 public key".to_string()),
+    };
+
+    let jwk = JWK::from(Params::EC(ECParams {
+        curve: Some("secp256k1".to_string()),
+        x_coordinate: Some(Base64urlUInt(x)),
+        y_coordinate: Some(Base64urlUInt(y)),
+        ecc_private_key: Some(Base64urlUInt(signing_key.to_bytes().to_vec())),
+    }));
+    manager.import_session_key_internal(jwk, key_id, false)
+}
+
+/// Get the EIP-55 checksummed Ethereum address of a secp256k1 session key.
+///
+/// # Arguments
+/// * `manager` - The session manager containing the key
+/// * `key_id` - Optional key ID (defaults to "default")
+///
+/// # Returns
+/// The `0x`-prefixed Ethereum address
+#[wasm_bindgen(js_name = getEthAddress)]
+pub fn get_eth_address(
+    manager: &TCWSessionManager,
+    key_id: Option<String>,
+) -> Result<String, String> {
+    use sha3::{Digest, Keccak256};
+
+    let jwk = manager.get_jwk(key_id)?;
+    let (x, y) = match &jwk.params {
+        Params::EC(ec_params) if ec_params.curve.as_deref() == Some("secp256k1") => {
+            match (&ec_params.x_coordinate, &ec_params.y_coordinate) {
+                (Some(x), Some(y)) if x.0.len() == 32 && y.0.len() == 32 => (&x.0, &y.0),
+                _ => return Err("secp256k1 key has invalid public coordinates".to_string()),
+            }
+        }
+        _ => return Err("Key is not a secp256k1 key".to_string()),
+    };
+
+    let mut public_key = Vec::with_capacity(64);
+    public_key.extend_from_slice(x);
+    public_key.extend_from_slice(y);
+    let hash = Keccak256::digest(public_key);
+
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&hash[12..]);
+    Ok(eip55(&address))
+}
+
+/// Format version of the blobs produced by [`export_key_encrypted`].
+const ENCRYPTED_KEY_VERSION: u8 = 1;
+/// PBKDF2-HMAC-SHA256 rounds used to derive the encryption key from the passphrase.
+const ENCRYPTED_KEY_PBKDF2_ROUNDS: u32 = 100_000;
+const ENCRYPTED_KEY_SALT_LEN: usize = 16;
+const ENCRYPTED_KEY_NONCE_LEN: usize = 12;
+
+fn derive_encryption_key(passphrase: &str, salt: &[u8]) -> aes_gcm::Aes256Gcm {
+    use aes_gcm::{Aes256Gcm, Key, KeyInit};
+
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<sha2::Sha256>(
+        passphrase.as_bytes(),
+        salt,
+        ENCRYPTED_KEY_PBKDF2_ROUNDS,
+        &mut key,
+    );
+    Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))
+}
+
+/// Export a private key as a passphrase-encrypted blob.
+///
+/// The JWK JSON is sealed with AES-256-GCM under a key derived from the passphrase
+/// with PBKDF2-HMAC-SHA256. The blob is base64 encoded as
+/// `version (1 byte) || salt (16 bytes) || nonce (12 bytes) || ciphertext`.
+///
+/// # Arguments
+/// * `manager` - The session manager containing the key
+/// * `key_id` - Optional key ID (defaults to "default")
+/// * `passphrase` - Passphrase used to encrypt the key
+///
+/// # Returns
+/// The encrypted key as a base64 string
+#[wasm_bindgen(js_name = exportKeyEncrypted)]
+pub fn export_key_encrypted(
+    manager: &TCWSessionManager,
+    key_id: Option<String>,
+    passphrase: String,
+) -> Result<String, String> {
+    use aes_gcm::{aead::Aead, Nonce};
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+    let jwk_json = export_key(manager, key_id)?;
+
+    let mut salt = [0u8; ENCRYPTED_KEY_SALT_LEN];
+    let mut nonce = [0u8; ENCRYPTED_KEY_NONCE_LEN];
+    getrandom::getrandom(&mut salt)
+        .and_then(|_| getrandom::getrandom(&mut nonce))
+        .map_err(|e| format!("Failed to generate randomness: {}", e))?;
+
+    let ciphertext = derive_encryption_key(&passphrase, &salt)
+        .encrypt(Nonce::from_slice(&nonce), jwk_json.as_bytes())
+        .map_err(|e| format!("Failed to encrypt key: {}", e))?;
+
+    let mut blob = Vec::with_capacity(1 + salt.len() + nonce.len() + ciphertext.len());
+    blob.push(ENCRYPTED_KEY_VERSION);
+    blob.extend_from_slice(&salt);
+    blob.extend_from_slice(&nonce);
+    blob.extend_from_slice(&ciphertext);
+    Ok(STANDARD.encode(blob))
+}
+
+/// Import a private key from a blob produced by [`export_key_encrypted`].
+///
+/// # Arguments
+/// * `manager` - The session manager to import the key into
+/// * `encrypted` - The encrypted key as a base64 string
+/// * `passphrase` - Passphrase the key was encrypted with
+/// * `key_id` - Optional key ID (defaults to "default")
+///
+/// # Returns
+/// The key ID of the imported key
+#[wasm_bindgen(js_name = importKeyEncrypted)]
+pub fn import_key_encrypted(
+    manager: &mut TCWSessionManager,
+    encrypted: String,
+    passphrase: String,
+    key_id: Option<String>,
+) -> Result<String, String> {
+    use aes_gcm::{aead::Aead, Nonce};
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+    let blob = STANDARD
+        .decode(encrypted.trim())
+        .map_err(|e| format!("Invalid base64 encoding for encrypted key: {}", e))?;
+    let (version, rest) = blob
+        .split_first()
+        .ok_or_else(|| "Encrypted key is empty".to_string())?;
+    if *version != ENCRYPTED_KEY_VERSION {
+        return Err(format!("Unsupported encrypted key version: {}", version));
+    }
+    if rest.len() < ENCRYPTED_KEY_SALT_LEN + ENCRYPTED_KEY_NONCE_LEN {
+        return Err("Encrypted key is truncated".to_string());
+    }
+    let (salt, rest) = rest.split_at(ENCRYPTED_KEY_SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(ENCRYPTED_KEY_NONCE_LEN);
+
+    let plaintext = derive_encryption_key(&passphrase, salt)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Failed to decrypt key: wrong passphrase or corrupted data".to_string())?;
+    let jwk_json = String::from_utf8(plaintext)
+        .map_err(|e| format!("Decrypted key is not valid UTF-8: {}", e))?;
+
+    import_key(manager, jwk_json, key_id)
+}
+
 /// Import a key from an environment variable value (JWK JSON string).
 /// Note: The actual environment variable reading happens in JavaScript.
 /// This function receives the already-read value.
@@ -76,25 +313,18 @@ pub fn import_key_from_env_value(
 /// The signature as bytes (64 bytes: r || s)
 #[wasm_bindgen(js_name = signSecp256k1)]
 pub fn sign_secp256k1(message: &[u8], private_key_hex: String) -> Result<Vec<u8>, String> {
-    use k256::ecdsa::{signature::Signer, Signature, SigningKey};
-
-    let hex_str = private_key_hex
-        .strip_prefix("0x")
-        .unwrap_or(&private_key_hex);
-    let key_bytes =
-        hex::decode(hex_str).map_err(|e| format!("Invalid hex encoding for private key: {}", e))?;
-
-    let signing_key = SigningKey::from_slice(&key_bytes)
-        .map_err(|e| format!("Invalid secp256k1 private key: {}", e))?;
+    use k256::ecdsa::{signature::Signer, Signature};
 
+    let signing_key = parse_secp256k1_key(&private_key_hex)?;
     let signature: Signature = signing_key.sign(message);
     Ok(signature.to_bytes().to_vec())
 }
 
 /// Sign an Ethereum message with the standard prefix and return the signature with recovery ID.
 ///
-/// This applies the Ethereum message prefix: "\x19Ethereum Signed Message:\n{length}{message}"
-/// and returns the signature as a hex string with the recovery byte appended (65 bytes total).
+/// This applies the Ethereum message prefix: "\x19Ethereum Signed Message:\n{length}{message}",
+/// where `{length}` is the UTF-8 byte length of the message, and returns the signature as a
+/// hex string with the recovery byte appended (65 bytes total).
 ///
 /// # Arguments
 /// * `message` - The message string to sign
@@ -104,24 +334,108 @@ pub fn sign_secp256k1(message: &[u8], private_key_hex: String) -> Result<Vec<u8>
 /// Hex-encoded signature (130 characters = 65 bytes: r || s || v)
 #[wasm_bindgen(js_name = signEthereumMessage)]
 pub fn sign_ethereum_message(message: String, private_key_hex: String) -> Result<String, String> {
-    use k256::ecdsa::SigningKey;
+    let signing_key = parse_secp256k1_key(&private_key_hex)?;
+    sign_prehash_recoverable(&signing_key, &eip191_hash(message.as_bytes()))
+}
+
+/// Sign a raw binary payload with the Ethereum message prefix, as
+/// [`sign_ethereum_message`] does for strings.
+///
+/// The `{length}` of the prefix is the number of bytes in `message`.
+///
+/// # Arguments
+/// * `message` - The bytes to sign
+/// * `private_key_hex` - Hex-encoded 32-byte private key (with or without 0x prefix)
+///
+/// # Returns
+/// Hex-encoded signature (130 characters = 65 bytes: r || s || v)
+#[wasm_bindgen(js_name = signEthereumMessageBytes)]
+pub fn sign_ethereum_message_bytes(
+    message: &[u8],
+    private_key_hex: String,
+) -> Result<String, String> {
+    let signing_key = parse_secp256k1_key(&private_key_hex)?;
+    sign_prehash_recoverable(&signing_key, &eip191_hash(message))
+}
+
+/// Sign several Ethereum messages with the same key, as [`sign_ethereum_message`] would.
+///
+/// The key is parsed once up front, so an invalid key fails before anything is signed.
+///
+/// # Arguments
+/// * `messages` - The message strings to sign
+/// * `private_key_hex` - Hex-encoded 32-byte private key (with or without 0x prefix)
+///
+/// # Returns
+/// Hex-encoded signatures, in the same order as `messages`
+#[wasm_bindgen(js_name = signEthereumMessagesBatch)]
+pub fn sign_ethereum_messages_batch(
+    messages: Vec<String>,
+    private_key_hex: String,
+) -> Result<Vec<String>, String> {
+    let signing_key = parse_secp256k1_key(&private_key_hex)?;
+    messages
+        .iter()
+        .map(|message| sign_prehash_recoverable(&signing_key, &eip191_hash(message.as_bytes())))
+        .collect()
+}
+
+/// Hash a message with the Ethereum message prefix: "\x19Ethereum Signed Message:\n{length}{message}"
+fn eip191_hash(message: &[u8]) -> [u8; 32] {
     use sha3::{Digest, Keccak256};
 
-    // Apply Ethereum message prefix
-    let prefixed = format!("\x19Ethereum Signed Message:\n{}{}", message.len(), message);
-    let hash = Keccak256::digest(prefixed.as_bytes());
+    let mut hasher = Keccak256::new();
+    hasher.update(format!("\x19Ethereum Signed Message:\n{}", message.len()));
+    hasher.update(message);
+    hasher.finalize().into()
+}
 
+/// Sign EIP-712 typed structured data with a secp256k1 private key.
+///
+/// `typed_json` is the standard `eth_signTypedData_v4` payload with `types`, `primaryType`,
+/// `domain` and `message`. If `types` does not declare `EIP712Domain`, it is derived from
+/// the fields present in `domain`.
+///
+/// # Arguments
+/// * `typed_json` - The EIP-712 typed data as a JSON string
+/// * `private_key_hex` - Hex-encoded 32-byte private key (with or without 0x prefix)
+///
+/// # Returns
+/// Hex-encoded signature (130 characters = 65 bytes: r || s || v)
+#[wasm_bindgen(js_name = signTypedData712)]
+pub fn sign_typed_data_712(typed_json: String, private_key_hex: String) -> Result<String, String> {
+    let typed_data: serde_json::Value = serde_json::from_str(&typed_json)
+        .map_err(|e| format!("Invalid EIP-712 typed data: {}", e))?;
+    let digest = eip712::digest(&typed_data)?;
+
+    let signing_key = parse_secp256k1_key(&private_key_hex)?;
+    sign_prehash_recoverable(&signing_key, &digest)
+}
+
+fn parse_secp256k1_key(private_key_hex: &str) -> Result<k256::ecdsa::SigningKey, String> {
     let hex_str = private_key_hex
         .strip_prefix("0x")
-        .unwrap_or(&private_key_hex);
+        .unwrap_or(private_key_hex);
     let key_bytes =
         hex::decode(hex_str).map_err(|e| format!("Invalid hex encoding for private key: {}", e))?;
+    if key_bytes.len() != 32 {
+        return Err(format!(
+            "Invalid secp256k1 private key: expected 32 bytes, got {}",
+            key_bytes.len()
+        ));
+    }
 
-    let signing_key = SigningKey::from_slice(&key_bytes)
-        .map_err(|e| format!("Invalid secp256k1 private key: {}", e))?;
+    k256::ecdsa::SigningKey::from_slice(&key_bytes)
+        .map_err(|e| format!("Invalid secp256k1 private key: {}", e))
+}
 
+/// Sign a 32-byte hash and return the hex signature with the Ethereum recovery byte appended.
+fn sign_prehash_recoverable(
+    signing_key: &k256::ecdsa::SigningKey,
+    hash: &[u8],
+) -> Result<String, String> {
     let (signature, recovery_id) = signing_key
-        .sign_prehash_recoverable(&hash)
+        .sign_prehash_recoverable(hash)
         .map_err(|e| format!("Signing failed: {}", e))?;
 
     // Combine signature bytes with recovery ID (Ethereum uses 27/28)
@@ -130,3 +444,582 @@ pub fn sign_ethereum_message(message: String, private_key_hex: String) -> Result
 
     Ok(hex::encode(sig_bytes))
 }
+
+/// EIP-712 `hashStruct` encoding over `serde_json` values.
+mod eip712 {
+    use std::collections::{BTreeSet, HashMap};
+
+    use serde_json::Value;
+    use sha3::{Digest, Keccak256};
+
+    /// Struct name -> ordered list of `(field name, field type)`.
+    type Types = HashMap<String, Vec<(String, String)>>;
+
+    const DOMAIN_TYPE: &str = "EIP712Domain";
+
+    /// Compute `keccak256(0x1901 || domainSeparator || hashStruct(message))`.
+    pub fn digest(typed_data: &Value) -> Result<[u8; 32], String> {
+        let domain = typed_data
+            .get("domain")
+            .ok_or("EIP-712 typed data is missing `domain`")?;
+        let message = typed_data
+            .get("message")
+            .ok_or("EIP-712 typed data is missing `message`")?;
+        let primary_type = typed_data
+            .get("primaryType")
+            .and_then(Value::as_str)
+            .ok_or("EIP-712 typed data is missing `primaryType`")?;
+        let mut types = parse_types(
+            typed_data
+                .get("types")
+                .ok_or("EIP-712 typed data is missing `types`")?,
+        )?;
+        if !types.contains_key(DOMAIN_TYPE) {
+            types.insert(DOMAIN_TYPE.to_string(), domain_fields(domain));
+        }
+        if !types.contains_key(primary_type) {
+            return Err(format!("Unknown EIP-712 primary type: {}", primary_type));
+        }
+
+        let mut encoded = vec![0x19, 0x01];
+        encoded.extend_from_slice(&hash_struct(DOMAIN_TYPE, domain, &types)?);
+        encoded.extend_from_slice(&hash_struct(primary_type, message, &types)?);
+        Ok(keccak(&encoded))
+    }
+
+    fn parse_types(types: &Value) -> Result<Types, String> {
+        let types = types
+            .as_object()
+            .ok_or("EIP-712 `types` must be an object")?;
+        types
+            .iter()
+            .map(|(name, fields)| {
+                let fields = fields
+                    .as_array()
+                    .ok_or_else(|| format!("EIP-712 type {} must be an array", name))?
+                    .iter()
+                    .map(|field| {
+                        match (
+                            field.get("name").and_then(Value::as_str),
+                            field.get("type").and_then(Value::as_str),
+                        ) {
+                            (Some(field_name), Some(field_type)) => {
+                                Ok((field_name.to_string(), field_type.to_string()))
+                            }
+                            _ => Err(format!("Invalid field in EIP-712 type {}", name)),
+                        }
+                    })
+                    .collect::<Result<Vec<_>, String>>()?;
+                Ok((name.clone(), fields))
+            })
+            .collect()
+    }
+
+    /// The `EIP712Domain` fields implied by the keys present in `domain`.
+    fn domain_fields(domain: &Value) -> Vec<(String, String)> {
+        [
+            ("name", "string"),
+            ("version", "string"),
+            ("chainId", "uint256"),
+            ("verifyingContract", "address"),
+            ("salt", "bytes32"),
+        ]
+        .into_iter()
+        .filter(|(name, _)| domain.get(name).is_some())
+        .map(|(name, field_type)| (name.to_string(), field_type.to_string()))
+        .collect()
+    }
+
+    fn keccak(bytes: &[u8]) -> [u8; 32] {
+        Keccak256::digest(bytes).into()
+    }
+
+    /// Strip any array suffixes, e.g. `Person[][2]` -> `Person`.
+    fn base_type(field_type: &str) -> &str {
+        field_type.split('[').next().unwrap_or(field_type)
+    }
+
+    fn collect_dependencies<'a>(
+        type_name: &'a str,
+        types: &'a Types,
+        found: &mut BTreeSet<&'a str>,
+    ) {
+        let type_name = base_type(type_name);
+        if found.contains(type_name) {
+            return;
+        }
+        if let Some(fields) = types.get(type_name) {
+            found.insert(type_name);
+            for (_, field_type) in fields {
+                collect_dependencies(field_type, types, found);
+            }
+        }
+    }
+
+    fn encode_type(primary_type: &str, types: &Types) -> String {
+        let mut dependencies = BTreeSet::new();
+        collect_dependencies(primary_type, types, &mut dependencies);
+        dependencies.remove(primary_type);
+
+        std::iter::once(primary_type)
+            .chain(dependencies)
+            .map(|name| {
+                let fields = types[name]
+                    .iter()
+                    .map(|(field_name, field_type)| format!("{} {}", field_type, field_name))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                format!("{}({})", name, fields)
+            })
+            .collect()
+    }
+
+    fn hash_struct(type_name: &str, value: &Value, types: &Types) -> Result<[u8; 32], String> {
+        let fields = types
+            .get(type_name)
+            .ok_or_else(|| format!("Unknown EIP-712 type: {}", type_name))?;
+
+        let mut encoded = keccak(encode_type(type_name, types).as_bytes()).to_vec();
+        for (field_name, field_type) in fields {
+            let field_value = value
+                .get(field_name)
+                .ok_or_else(|| format!("Missing EIP-712 field {}.{}", type_name, field_name))?;
+            encoded.extend_from_slice(&encode_value(field_type, field_value, types)?);
+        }
+        Ok(keccak(&encoded))
+    }
+
+    fn encode_value(field_type: &str, value: &Value, types: &Types) -> Result<[u8; 32], String> {
+        if let Some(element_type) = field_type
+            .strip_suffix(']')
+            .and_then(|t| t.rsplit_once('['))
+            .map(|(element_type, _)| element_type)
+        {
+            let elements = value
+                .as_array()
+                .ok_or_else(|| format!("Expected an array for EIP-712 type {}", field_type))?;
+            let mut encoded = Vec::with_capacity(elements.len() * 32);
+            for element in elements {
+                encoded.extend_from_slice(&encode_value(element_type, element, types)?);
+            }
+            return Ok(keccak(&encoded));
+        }
+
+        if types.contains_key(field_type) {
+            return hash_struct(field_type, value, types);
+        }
+
+        let type_error = || format!("Invalid value for EIP-712 type {}: {}", field_type, value);
+        match field_type {
+            "string" => Ok(keccak(value.as_str().ok_or_else(type_error)?.as_bytes())),
+            "bytes" => Ok(keccak(&decode_hex(value).ok_or_else(type_error)?)),
+            "bool" => {
+                let mut word = [0u8; 32];
+                word[31] = value.as_bool().ok_or_else(type_error)? as u8;
+                Ok(word)
+            }
+            "address" => {
+                let bytes = decode_hex(value)
+                    .filter(|bytes| bytes.len() == 20)
+                    .ok_or_else(type_error)?;
+                let mut word = [0u8; 32];
+                word[12..].copy_from_slice(&bytes);
+                Ok(word)
+            }
+            _ => {
+                if let Some(size) = field_type.strip_prefix("bytes") {
+                    let size: usize = size
+                        .parse()
+                        .ok()
+                        .filter(|size| (1..=32).contains(size))
+                        .ok_or_else(|| format!("Unknown EIP-712 type: {}", field_type))?;
+                    let bytes = decode_hex(value)
+                        .filter(|bytes| bytes.len() == size)
+                        .ok_or_else(type_error)?;
+                    let mut word = [0u8; 32];
+                    word[..size].copy_from_slice(&bytes);
+                    return Ok(word);
+                }
+                let signed = if field_type.starts_with("uint") {
+                    false
+                } else if field_type.starts_with("int") {
+                    true
+                } else {
+                    return Err(format!("Unknown EIP-712 type: {}", field_type));
+                };
+                encode_integer(value, signed).ok_or_else(type_error)
+            }
+        }
+    }
+
+    fn decode_hex(value: &Value) -> Option<Vec<u8>> {
+        let s = value.as_str()?;
+        hex::decode(s.strip_prefix("0x").unwrap_or(s)).ok()
+    }
+
+    /// Encode a JSON number or decimal/`0x` hex string as a 256-bit big-endian word.
+    fn encode_integer(value: &Value, signed: bool) -> Option<[u8; 32]> {
+        let (negative, magnitude) = match value {
+            Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+                (Some(n), _) => (false, n.to_string()),
+                (None, Some(n)) => (true, n.unsigned_abs().to_string()),
+                _ => return None,
+            },
+            Value::String(s) => match s.strip_prefix('-') {
+                Some(rest) => (true, rest.to_string()),
+                None => (false, s.clone()),
+            },
+            _ => return None,
+        };
+        if negative && !signed {
+            return None;
+        }
+
+        let mut word = [0u8; 32];
+        if let Some(hex_digits) = magnitude.strip_prefix("0x") {
+            let padded = format!("{:0>64}", hex_digits);
+            if padded.len() > 64 {
+                return None;
+            }
+            hex::decode_to_slice(padded, &mut word).ok()?;
+        } else {
+            if magnitude.is_empty() {
+                return None;
+            }
+            for digit in magnitude.chars() {
+                let mut carry = digit.to_digit(10)?;
+                for byte in word.iter_mut().rev() {
+                    let acc = *byte as u32 * 10 + carry;
+                    *byte = acc as u8;
+                    carry = acc >> 8;
+                }
+                if carry != 0 {
+                    return None;
+                }
+            }
+        }
+
+        if negative {
+            // Two's complement
+            for byte in word.iter_mut() {
+                *byte = !*byte;
+            }
+            for byte in word.iter_mut().rev() {
+                let (sum, overflow) = byte.overflowing_add(1);
+                *byte = sum;
+                if !overflow {
+                    break;
+                }
+            }
+        }
+        Some(word)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn private_param(manager: &TCWSessionManager, key_id: &str) -> serde_json::Value {
+        let jwk: serde_json::Value =
+            serde_json::from_str(&manager.jwk(Some(key_id.to_string())).unwrap()).unwrap();
+        jwk["d"].clone()
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_key_round_trip() {
+        let source = TCWSessionManager::new().unwrap();
+        let encrypted = export_key_encrypted(&source, None, "correct horse".to_string()).unwrap();
+
+        let mut target = TCWSessionManager::new().unwrap();
+        let key_id = import_key_encrypted(
+            &mut target,
+            encrypted,
+            "correct horse".to_string(),
+            Some("restored".to_string()),
+        )
+        .unwrap();
+        assert_eq!(key_id, "restored");
+        assert_eq!(
+            private_param(&source, "default"),
+            private_param(&target, "restored")
+        );
+    }
+
+    fn test_jwks() -> String {
+        let mut first = JWK::generate_ed25519().unwrap();
+        first.key_id = Some("signing".to_string());
+        let second = JWK::generate_secp256k1();
+        serde_json::json!({ "keys": [first, second] }).to_string()
+    }
+
+    #[tokio::test]
+    async fn test_import_jwks() {
+        let mut manager = TCWSessionManager::new().unwrap();
+        let jwks = test_jwks();
+        let key_ids = import_jwks(
+            &mut manager,
+            jwks.clone(),
+            Some("backend-".to_string()),
+            false,
+        )
+        .unwrap();
+        assert_eq!(key_ids, vec!["backend-signing", "backend-1"]);
+
+        let set: serde_json::Value = serde_json::from_str(&jwks).unwrap();
+        assert_eq!(
+            private_param(&manager, "backend-signing"),
+            set["keys"][0]["d"]
+        );
+        assert_eq!(private_param(&manager, "backend-1"), set["keys"][1]["d"]);
+    }
+
+    #[tokio::test]
+    async fn test_import_jwks_existing_keys() {
+        let mut manager = TCWSessionManager::new().unwrap();
+        import_jwks(&mut manager, test_jwks(), None, false).unwrap();
+
+        let jwks = test_jwks();
+        assert!(import_jwks(&mut manager, jwks.clone(), None, false).is_err());
+        let set: serde_json::Value = serde_json::from_str(&jwks).unwrap();
+        assert_ne!(private_param(&manager, "signing"), set["keys"][0]["d"]);
+
+        import_jwks(&mut manager, jwks, None, true).unwrap();
+        assert_eq!(private_param(&manager, "signing"), set["keys"][0]["d"]);
+        assert_eq!(private_param(&manager, "1"), set["keys"][1]["d"]);
+    }
+
+    #[tokio::test]
+    async fn test_import_jwks_public_key() {
+        let mut manager = TCWSessionManager::new().unwrap();
+        let private = JWK::generate_ed25519().unwrap();
+        let public = JWK::generate_secp256k1().to_public();
+        let jwks = serde_json::json!({ "keys": [private, public] }).to_string();
+        assert_eq!(
+            import_jwks(&mut manager, jwks, None, false),
+            Err("session key 1 has no private key".to_string())
+        );
+        assert!(!manager.has_key("0"));
+        assert!(!manager.has_key("1"));
+    }
+
+    #[tokio::test]
+    async fn test_import_jwks_malformed() {
+        let mut manager = TCWSessionManager::new().unwrap();
+        for jwks in [
+            "not json",
+            r#"{"keys": 5}"#,
+            r#"{"keys": [{"kty": "nope"}]}"#,
+            "{}",
+        ] {
+            assert!(import_jwks(&mut manager, jwks.to_string(), None, false).is_err());
+        }
+        assert!(export_key(&manager, Some("0".to_string())).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_key_wrong_passphrase() {
+        let source = TCWSessionManager::new().unwrap();
+        let encrypted = export_key_encrypted(&source, None, "correct horse".to_string()).unwrap();
+
+        let mut target = TCWSessionManager::new().unwrap();
+        let result = import_key_encrypted(
+            &mut target,
+            encrypted,
+            "battery staple".to_string(),
+            Some("restored".to_string()),
+        );
+        assert!(result.unwrap_err().contains("wrong passphrase"));
+        assert!(target.getDID(Some("restored".to_string())).is_err());
+    }
+
+    const TEST_SECP256K1_KEY: &str =
+        "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+    const TEST_SECP256K1_ADDRESS: &str = "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23";
+
+    #[tokio::test]
+    async fn test_import_secp256k1_key_hex() {
+        let mut manager = TCWSessionManager::new().unwrap();
+        let key_id = import_secp256k1_key_hex(
+            &mut manager,
+            TEST_SECP256K1_KEY.to_string(),
+            Some("eth".to_string()),
+        )
+        .unwrap();
+        assert_eq!(key_id, "eth");
+        assert_eq!(
+            get_eth_address(&manager, Some("eth".to_string())).unwrap(),
+            TEST_SECP256K1_ADDRESS
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_secp256k1_key_hex_invalid() {
+        let mut manager = TCWSessionManager::new().unwrap();
+        for key in [
+            // Wrong length
+            "0x4c0883a69102937d6231471b5dbb6204",
+            // Zero scalar
+            "0x0000000000000000000000000000000000000000000000000000000000000000",
+            // Scalar above the curve order
+            "0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+        ] {
+            let result =
+                import_secp256k1_key_hex(&mut manager, key.to_string(), Some("eth".to_string()));
+            assert!(result.is_err(), "{} should be rejected", key);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_eth_address_ed25519_key() {
+        let manager = TCWSessionManager::new().unwrap();
+        assert!(get_eth_address(&manager, None).is_err());
+    }
+
+    /// Recover the EIP-55 address that produced an Ethereum message signature.
+    fn recover_address(message: &[u8], signature_hex: &str) -> String {
+        use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+        use k256::elliptic_curve::sec1::ToEncodedPoint;
+        use sha3::{Digest, Keccak256};
+
+        let sig_bytes = hex::decode(signature_hex).unwrap();
+        let signature = Signature::from_slice(&sig_bytes[..64]).unwrap();
+        let recovery_id = RecoveryId::from_byte(sig_bytes[64] - 27).unwrap();
+        let verifying_key =
+            VerifyingKey::recover_from_prehash(&eip191_hash(message), &signature, recovery_id)
+                .unwrap();
+        let public_key = k256::PublicKey::from(&verifying_key);
+        let point = ToEncodedPoint::to_encoded_point(&public_key, false);
+        let hash = Keccak256::digest(&point.as_bytes()[1..]);
+        eip55(hash[12..].try_into().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_sign_ethereum_message_non_ascii() {
+        let message = "héllo wörld 🌍";
+        assert_eq!(message.chars().count(), 13);
+        assert_eq!(message.len(), 18);
+
+        let signature =
+            sign_ethereum_message(message.to_string(), TEST_SECP256K1_KEY.to_string()).unwrap();
+        assert_eq!(
+            signature,
+            "a77ff700ae2cf1aec6a2bc6866d40e6602ed9a4e87cf5cd3f8e639399b7ca296\
+             17822e038e46769984e2d6ddd631917eb0aa92f83df62188fe7f8b1f654d416e1b"
+        );
+        assert_eq!(
+            recover_address(message.as_bytes(), &signature),
+            TEST_SECP256K1_ADDRESS
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sign_ethereum_message_bytes() {
+        let message = [0x00, 0x9f, 0x92, 0x96, 0xff];
+        let signature =
+            sign_ethereum_message_bytes(&message, TEST_SECP256K1_KEY.to_string()).unwrap();
+        assert_eq!(
+            signature,
+            "95456a2f0a953984b53b891756ad8dcbda08c6230a5bae626abe8998febc7e77\
+             24c7a11a732a0e2a3c37f01bff0b57238208fea14076c29547bfc12afd00b7ba1c"
+        );
+        assert_eq!(
+            recover_address(&message, &signature),
+            TEST_SECP256K1_ADDRESS
+        );
+        assert_eq!(
+            sign_ethereum_message_bytes(b"first", TEST_SECP256K1_KEY.to_string()),
+            sign_ethereum_message("first".to_string(), TEST_SECP256K1_KEY.to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sign_ethereum_messages_batch() {
+        let messages = vec!["first".to_string(), "second".to_string(), String::new()];
+        let batch =
+            sign_ethereum_messages_batch(messages.clone(), TEST_SECP256K1_KEY.to_string()).unwrap();
+        let individual: Vec<String> = messages
+            .into_iter()
+            .map(|message| sign_ethereum_message(message, TEST_SECP256K1_KEY.to_string()).unwrap())
+            .collect();
+        assert_eq!(batch, individual);
+    }
+
+    #[tokio::test]
+    async fn test_sign_ethereum_messages_batch_empty() {
+        let batch = sign_ethereum_messages_batch(vec![], TEST_SECP256K1_KEY.to_string()).unwrap();
+        assert!(batch.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sign_ethereum_messages_batch_invalid_key() {
+        let result =
+            sign_ethereum_messages_batch(vec!["first".to_string()], "0xnothex".to_string());
+        assert!(result.is_err());
+    }
+
+    /// The `Mail` example from the EIP-712 specification.
+    const EIP712_MAIL: &str = r#"{
+        "types": {
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "version", "type": "string" },
+                { "name": "chainId", "type": "uint256" },
+                { "name": "verifyingContract", "type": "address" }
+            ],
+            "Person": [
+                { "name": "name", "type": "string" },
+                { "name": "wallet", "type": "address" }
+            ],
+            "Mail": [
+                { "name": "from", "type": "Person" },
+                { "name": "to", "type": "Person" },
+                { "name": "contents", "type": "string" }
+            ]
+        },
+        "primaryType": "Mail",
+        "domain": {
+            "name": "Ether Mail",
+            "version": "1",
+            "chainId": 1,
+            "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+        },
+        "message": {
+            "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+            "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+            "contents": "Hello, Bob!"
+        }
+    }"#;
+
+    /// keccak256("cow"), the signer of the EIP-712 specification example.
+    const EIP712_MAIL_KEY: &str =
+        "c85ef7d79691fe79573b1a7064c19c1a9819ebdbd1faaab1a8ec92344438aaf4";
+
+    #[tokio::test]
+    async fn test_sign_typed_data_712() {
+        let signature =
+            sign_typed_data_712(EIP712_MAIL.to_string(), EIP712_MAIL_KEY.to_string()).unwrap();
+        assert_eq!(
+            signature,
+            "4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d\
+             07299936d304c153f6443dfa05f40ff007d72911b6f72307f996231605b91562\
+             1c"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sign_typed_data_712_missing_primary_type() {
+        let mut typed: serde_json::Value = serde_json::from_str(EIP712_MAIL).unwrap();
+        typed.as_object_mut().unwrap().remove("primaryType");
+        let result = sign_typed_data_712(typed.to_string(), EIP712_MAIL_KEY.to_string());
+        assert!(result.unwrap_err().contains("primaryType"));
+    }
+
+    #[tokio::test]
+    async fn test_sign_typed_data_712_unknown_type() {
+        let mut typed: serde_json::Value = serde_json::from_str(EIP712_MAIL).unwrap();
+        typed["types"]["Mail"][0]["type"] = "Sender".into();
+        let result = sign_typed_data_712(typed.to_string(), EIP712_MAIL_KEY.to_string());
+        assert!(result.unwrap_err().contains("Unknown EIP-712 type"));
+    }
+}
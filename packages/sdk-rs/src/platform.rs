@@ -8,6 +8,12 @@ use wasm_bindgen::prelude::*;
 extern "C" {
     #[wasm_bindgen(js_namespace = console)]
     fn error(s: &str);
+
+    #[wasm_bindgen(js_namespace = console)]
+    fn warn(s: &str);
+
+    #[wasm_bindgen(js_namespace = console)]
+    fn info(s: &str);
 }
 
 /// Log an error message to the console.
@@ -26,5 +32,68 @@ pub fn log_error(msg: &str) {
 #[cfg(not(any(feature = "browser", feature = "nodejs")))]
 pub fn log_error(msg: &str) {
     // In test/default mode, just print to stderr
-    eprintln!("{}", msg);
+    write_log(&mut std::io::stderr(), "error", msg);
+}
+
+/// Log a warning, for problems that do not stop the current operation.
+#[cfg(feature = "browser")]
+pub fn log_warn(msg: &str) {
+    web_sys::console::warn_1(&msg.into());
+}
+
+#[cfg(feature = "nodejs")]
+pub fn log_warn(msg: &str) {
+    warn(msg);
+}
+
+#[cfg(not(any(feature = "browser", feature = "nodejs")))]
+pub fn log_warn(msg: &str) {
+    write_log(&mut std::io::stderr(), "warn", msg);
+}
+
+/// Log an informational diagnostic.
+#[cfg(feature = "browser")]
+pub fn log_info(msg: &str) {
+    web_sys::console::info_1(&msg.into());
+}
+
+#[cfg(feature = "nodejs")]
+pub fn log_info(msg: &str) {
+    info(msg);
+}
+
+#[cfg(not(any(feature = "browser", feature = "nodejs")))]
+pub fn log_info(msg: &str) {
+    write_log(&mut std::io::stderr(), "info", msg);
+}
+
+/// Write a log line for the stderr fallback, tagged with its level.
+#[cfg(not(any(feature = "browser", feature = "nodejs")))]
+fn write_log(out: &mut impl std::io::Write, level: &str, msg: &str) {
+    // logging must never fail the operation being logged
+    let _ = writeln!(out, "[{}] {}", level, msg);
+}
+
+#[cfg(all(test, not(any(feature = "browser", feature = "nodejs"))))]
+mod test {
+    use super::*;
+
+    /// Checks the format of the fallback lines, which the stderr fallback writes unchanged.
+    #[tokio::test]
+    async fn test_write_log_format() {
+        let mut out = Vec::new();
+        write_log(&mut out, "error", "Invalid action 'not an action'");
+        write_log(&mut out, "warn", "Replacing session key: default");
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "[error] Invalid action 'not an action'\n[warn] Replacing session key: default\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_log_fallback_does_not_panic() {
+        log_error("error");
+        log_warn("warn");
+        log_info("info");
+    }
 }
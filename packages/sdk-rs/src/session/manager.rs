@@ -1,57 +1,281 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+    str::FromStr,
+};
 
+use base64::{engine::general_purpose::STANDARD, Engine as _};
 use iri_string::types::UriString;
 use js_sys::JsString;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use time::{format_description::well_known::Rfc3339, Duration, OffsetDateTime};
 use tinycloud_sdk_rs::tinycloud_auth::{
-    cacaos::siwe::{generate_nonce, Message, Version as SiweVersion},
+    cacaos::siwe::{eip55, generate_nonce, Message, Version as SiweVersion},
     siwe_recap::{Ability, Capability},
-    ssi::{dids::DIDKey, jwk::JWK},
+    ssi::{
+        dids::DIDKey,
+        jwk::{Algorithm, Base64urlUInt, OctetParams, Params, JWK},
+        jws,
+    },
 };
 use wasm_bindgen::prelude::*;
 
+use super::recap::{decode_recap_uri, is_recap_uri, merge_capabilities, message_recap_json};
 use super::types::*;
-use crate::platform::log_error;
+use crate::platform::{log_error, log_info, log_warn};
 
 use tinycloud_sdk_wasm::session::Session;
 
-#[derive(Debug, Default)]
+/// A built SIWE message alongside the parts a UI may want to render separately.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildDetails {
+    /// The full SIWE message to be signed, identical to the output of `build`.
+    pub message: String,
+    pub statement: Option<String>,
+    pub uri: String,
+    pub decoded_recap: Value,
+}
+
+/// The public details of a session key, for debugging.
+///
+/// Never contains private key material.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeySummary {
+    pub key_id: String,
+    pub did: String,
+    pub public_jwk: JWK,
+    pub alg: Option<Algorithm>,
+    pub has_session: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SessionInfo {
     key: Option<JWK>,
     session: Option<Session>,
+    /// The signed SIWE message the session was created from.
+    #[serde(default)]
+    siwe: Option<String>,
+    /// Position of the key in creation order.
+    #[serde(default)]
+    seq: u64,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionManager {
     sessions: HashMap<String, SessionInfo>,
     capability: Capability<Value>,
+    /// Sequence number handed to the next created or imported key.
+    #[serde(default)]
+    next_seq: u64,
+    /// DID method of the identity returned by `get_did`.
+    #[serde(default)]
+    did_method: DidMethodKind,
+    /// Maximum number of actions the capability may hold.
+    #[serde(default = "default_max_abilities")]
+    max_abilities: usize,
 }
 
 static DEFAULT_KEY_ID: &str = "default";
 
+/// Default cap on the number of actions in a capability.
+const DEFAULT_MAX_ABILITIES: usize = 1000;
+
+/// Largest recap resource, in bytes, that a built message may carry.
+const MAX_RECAP_BYTES: usize = 64 * 1024;
+
+fn default_max_abilities() -> usize {
+    DEFAULT_MAX_ABILITIES
+}
+
+/// DID method of the identity returned by [`SessionManager::get_did`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum DidMethodKind {
+    /// `did:key` verification method of the session key.
+    #[default]
+    Key,
+    /// `did:pkh:eip155` identity of an Ethereum account.
+    Pkh { chain_id: u64, address: String },
+}
+
+impl FromStr for DidMethodKind {
+    type Err = String;
+
+    /// Parse `key` or `pkh:eip155:<chain id>:<address>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "key" {
+            return Ok(DidMethodKind::Key);
+        }
+        let invalid = || {
+            format!(
+                "unsupported DID method '{}', expected 'key' or 'pkh:eip155:<chain id>:<address>'",
+                s
+            )
+        };
+        let (chain_id, address) = s
+            .strip_prefix("pkh:eip155:")
+            .and_then(|account| account.split_once(':'))
+            .ok_or_else(invalid)?;
+        let chain_id = chain_id.parse().map_err(|_| invalid())?;
+        pkh_address_hex(address)?;
+        Ok(DidMethodKind::Pkh {
+            chain_id,
+            address: address.to_string(),
+        })
+    }
+}
+
+/// Error returned by session key management, with a stable `code` for JS callers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionError {
+    KeyExists(String),
+    KeyNotFound(String),
+    InvalidJwk(String),
+    ParseError(String),
+    Crypto(String),
+}
+
+impl SessionError {
+    /// Machine-readable code of the error, exposed to JS as `code`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            SessionError::KeyExists(_) => "KeyExists",
+            SessionError::KeyNotFound(_) => "KeyNotFound",
+            SessionError::InvalidJwk(_) => "InvalidJwk",
+            SessionError::ParseError(_) => "ParseError",
+            SessionError::Crypto(_) => "Crypto",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            SessionError::KeyExists(message)
+            | SessionError::KeyNotFound(message)
+            | SessionError::InvalidJwk(message)
+            | SessionError::ParseError(message)
+            | SessionError::Crypto(message) => message,
+        }
+    }
+
+    /// The `{ code, message }` object JS callers receive for this error.
+    fn js_error(&self) -> JsSessionError<'_> {
+        JsSessionError {
+            code: self.code(),
+            message: self.message(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsSessionError<'a> {
+    code: &'a str,
+    message: &'a str,
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+impl From<SessionError> for String {
+    fn from(error: SessionError) -> Self {
+        error.message().to_string()
+    }
+}
+
+impl From<SessionError> for JsValue {
+    fn from(error: SessionError) -> Self {
+        serde_wasm_bindgen::to_value(&error.js_error())
+            .unwrap_or_else(|_| JsValue::from_str(error.message()))
+    }
+}
+
+/// Signature algorithm of a generated session key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyAlgorithm {
+    #[default]
+    Ed25519,
+    Secp256k1,
+    /// NIST P-256 (secp256r1), as used by WebAuthn passkeys.
+    P256,
+}
+
+impl KeyAlgorithm {
+    /// The algorithm of an existing key, if it is one this manager can generate.
+    fn of(key: &JWK) -> Option<KeyAlgorithm> {
+        match &key.params {
+            Params::OKP(params) if params.curve == "Ed25519" => Some(KeyAlgorithm::Ed25519),
+            Params::EC(params) => match params.curve.as_deref() {
+                Some("secp256k1") => Some(KeyAlgorithm::Secp256k1),
+                Some("P-256") => Some(KeyAlgorithm::P256),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn generate(self) -> Result<JWK, String> {
+        match self {
+            KeyAlgorithm::Ed25519 => JWK::generate_ed25519()
+                .map_err(|error| format!("failed to generate session key: {}", error)),
+            KeyAlgorithm::Secp256k1 => Ok(JWK::generate_secp256k1()),
+            KeyAlgorithm::P256 => Ok(JWK::generate_p256()),
+        }
+    }
+}
+
+impl fmt::Display for KeyAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            KeyAlgorithm::Ed25519 => "Ed25519",
+            KeyAlgorithm::Secp256k1 => "secp256k1",
+            KeyAlgorithm::P256 => "P-256",
+        })
+    }
+}
+
+impl FromStr for KeyAlgorithm {
+    type Err = SessionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Ed25519" => Ok(KeyAlgorithm::Ed25519),
+            "secp256k1" => Ok(KeyAlgorithm::Secp256k1),
+            "P-256" => Ok(KeyAlgorithm::P256),
+            _ => Err(SessionError::ParseError(format!(
+                "unsupported key algorithm '{}', expected one of Ed25519, secp256k1, P-256",
+                s
+            ))),
+        }
+    }
+}
+
 /// Builds an TCWSession.
 impl SessionManager {
     /// Initialize a new SessionManager.
     pub fn new() -> Result<SessionManager, String> {
-        let key_id = DEFAULT_KEY_ID.to_string();
-        let mut sessions: HashMap<String, SessionInfo> = HashMap::new();
-        let mut default_key: JWK = JWK::generate_ed25519()
-            .map_err(|error| format!("failed to generate session key: {}", error))?;
-
-        // add key_id to jwk
-        default_key.key_id = Some(key_id.clone());
+        let mut manager = SessionManager::empty();
+        manager.create_session_key(Some(DEFAULT_KEY_ID.to_string()))?;
+        Ok(manager)
+    }
 
-        sessions.insert(
-            key_id,
-            SessionInfo {
-                key: Some(default_key),
-                session: None,
-            },
-        );
-        Ok(Self {
-            sessions,
+    /// Initialize a SessionManager without any session keys, for callers that import
+    /// their own key.
+    pub fn empty() -> SessionManager {
+        Self {
+            sessions: HashMap::new(),
             capability: Capability::default(),
-        })
+            next_seq: 0,
+            did_method: DidMethodKind::default(),
+            max_abilities: DEFAULT_MAX_ABILITIES,
+        }
     }
 
     // reset the builder
@@ -59,6 +283,121 @@ impl SessionManager {
         self.capability = Capability::default();
     }
 
+    /// Restore the manager to the state of [`SessionManager::new`]: a single fresh
+    /// `default` key and an empty capability. All other keys and sessions are dropped.
+    pub fn reset_all(&mut self) -> Result<(), String> {
+        *self = SessionManager::new()?;
+        Ok(())
+    }
+
+    /// Limit the number of actions the capability may hold; adding actions beyond it fails.
+    pub fn set_max_abilities(&mut self, max: usize) {
+        self.max_abilities = max;
+    }
+
+    /// Total number of actions across all targets of the capability.
+    fn ability_count(&self) -> usize {
+        self.capability
+            .abilities()
+            .values()
+            .map(|abilities| abilities.len())
+            .sum()
+    }
+
+    /// Whether any actions have been added to the capability.
+    pub fn has_capabilities(&self) -> bool {
+        !self.capability.abilities().is_empty()
+    }
+
+    /// The actions added to the capability, keyed by target.
+    pub fn list_abilities(&self) -> BTreeMap<String, Vec<String>> {
+        abilities_by_target(&self.capability)
+    }
+
+    /// Remove every target whose URI is under `namespace` (e.g. `kv` removes all
+    /// `kv:` targets), returning whether anything was removed.
+    pub fn remove_namespace(&mut self, namespace: &str) -> bool {
+        let prefix = format!("{}:", namespace);
+        if !self
+            .capability
+            .abilities()
+            .keys()
+            .any(|target| target.as_str().starts_with(&prefix))
+        {
+            return false;
+        }
+
+        // the capability has no API to remove a target, so rebuild it without them
+        let mut capability = match serde_json::to_value(&self.capability) {
+            Ok(capability) => capability,
+            Err(e) => {
+                log_error(&format!("Failed to serialize capability: {}", e));
+                return false;
+            }
+        };
+        if let Some(att) = capability.get_mut("att").and_then(Value::as_object_mut) {
+            att.retain(|target, _| !target.starts_with(&prefix));
+        }
+        match serde_json::from_value(capability) {
+            Ok(capability) => {
+                self.capability = capability;
+                true
+            }
+            Err(e) => {
+                log_error(&format!("Failed to rebuild capability: {}", e));
+                false
+            }
+        }
+    }
+
+    /// A single-line summary of the actions of the capability and of who is signing,
+    /// if there is anything to summarize.
+    fn capability_statement(
+        capability: &Capability<Value>,
+        address_label: Option<&str>,
+    ) -> Option<String> {
+        let mut parts = Vec::new();
+        if let Some(label) = address_label {
+            // the statement must not contain line breaks
+            let label: String = label
+                .chars()
+                .map(|c| if c.is_control() { ' ' } else { c })
+                .collect();
+            parts.push(format!("Signing in as {}.", label.trim()));
+        }
+        let abilities = abilities_by_target(capability);
+        if !abilities.is_empty() {
+            let grants = abilities
+                .iter()
+                .map(|(target, actions)| format!("{} on {}", actions.join(", "), target))
+                .collect::<Vec<_>>()
+                .join("; ");
+            parts.push(format!(
+                "This request will allow the application to perform: {}.",
+                grants
+            ));
+        }
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join(" "))
+        }
+    }
+
+    /// Serialize the capability to JSON, e.g. to hand it to another manager.
+    pub fn export_capability(&self) -> Result<String, String> {
+        serde_json::to_string(&self.capability)
+            .map_err(|e| format!("failed to serialize capability: {}", e))
+    }
+
+    /// Replace the capability with one from the output of
+    /// [`SessionManager::export_capability`].
+    pub fn import_capability(&mut self, json: String) -> Result<(), String> {
+        self.capability = serde_json::from_str(&json)
+            .map_err(|e| format!("failed to deserialize capability: {}", e))?;
+        Ok(())
+    }
+
     /// Build a SIWE message for signing.
     pub fn build(
         self,
@@ -66,69 +405,160 @@ impl SessionManager {
         key_id: Option<String>,
         custom_uri: Option<String>,
     ) -> Result<String, JsValue> {
-        let did_uri_string = match custom_uri {
-            Some(uri) => uri,
-            None => self.get_did(key_id)?,
+        let params = SiweParams::try_from(&config)?;
+        let siwe = self.build_message(params, key_id, custom_uri)?;
+        Ok(siwe.to_string())
+    }
+
+    /// Build a SIWE message that expires `valid_for_secs` after `issuedAt`.
+    ///
+    /// An explicit `expirationTime` in the config takes precedence.
+    pub fn build_with_duration(
+        self,
+        config: SiweConfig,
+        key_id: Option<String>,
+        custom_uri: Option<String>,
+        valid_for_secs: Option<u64>,
+    ) -> Result<String, JsValue> {
+        let mut params = SiweParams::try_from(&config)?;
+        params.valid_for_secs = valid_for_secs;
+        let siwe = self.build_message(params, key_id, custom_uri)?;
+        Ok(siwe.to_string())
+    }
+
+    /// Build a SIWE message and return it with its statement, URI and decoded recap.
+    pub fn build_detailed(
+        self,
+        config: SiweConfig,
+        key_id: Option<String>,
+        custom_uri: Option<String>,
+    ) -> Result<JsValue, JsValue> {
+        let params = SiweParams::try_from(&config)?;
+        let details = self.build_details(params, key_id, custom_uri)?;
+        details
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(JsValue::from)
+    }
+
+    pub(crate) fn build_details(
+        &self,
+        params: SiweParams,
+        key_id: Option<String>,
+        custom_uri: Option<String>,
+    ) -> Result<BuildDetails, String> {
+        let siwe = self.build_message(params, key_id, custom_uri)?;
+        Ok(BuildDetails {
+            message: siwe.to_string(),
+            statement: siwe.statement.clone(),
+            uri: siwe.uri.to_string(),
+            decoded_recap: message_recap_json(&siwe)?,
+        })
+    }
+
+    /// Build the SIWE message from already-extracted config fields.
+    pub(crate) fn build_message(
+        &self,
+        params: SiweParams,
+        key_id: Option<String>,
+        custom_uri: Option<String>,
+    ) -> Result<Message, String> {
+        let uri = match custom_uri {
+            Some(custom_uri) => parse_custom_uri(&custom_uri)?,
+            None => UriString::from_str(&self.get_did_vm(key_id)?)
+                .map_err(|e| format!("Failed to convert URI string to RiString: {}", e))?,
         };
 
-        let uri = iri_string::types::UriString::from_str(&did_uri_string)
-            .map_err(|e| format!("Failed to convert URI string to RiString: {}", e))?;
+        if params.chain_id == 0 {
+            return Err("invalid chain id: chain id must be non-zero".to_string());
+        }
 
-        let domain = config
-            .domain()
+        let domain = params
+            .domain
             .parse()
             .map_err(|e| format!("failed to parse the domain as an authority: {}", e))?;
-        let addr = config.address();
-        let address =
-            tinycloud_sdk_rs::util::decode_eip55(addr.strip_prefix("0x").unwrap_or(&addr))
-                .map_err(|e| format!("failed to parse '{}' as an Eth Address: {}", addr, e))?;
-        let nonce = config.nonce().unwrap_or_else(generate_nonce);
+        let address = validate_address(&params.address)?;
+        let nonce = params.nonce.unwrap_or_else(generate_nonce);
         let parse_date_err = |e| format!("unable to parse timestamp from string: {}", e);
-        let issued_at = config.issuedAt().parse().map_err(parse_date_err)?;
-        let expiration_time = config
-            .expirationTime()
+        let issued_at = params.issued_at.parse().map_err(parse_date_err)?;
+        let expiration_time = match (params.expiration_time, params.valid_for_secs) {
+            (Some(expiration_time), _) => Some(expiration_time),
+            (None, Some(secs)) => Some(offset_timestamp(&params.issued_at, secs)?),
+            (None, None) => None,
+        };
+        let expiration_time = expiration_time
             .map(|s| s.parse().map_err(parse_date_err))
             .transpose()?;
-        let not_before = config
-            .notBefore()
+        let not_before = params
+            .not_before
             .map(|s| s.parse().map_err(parse_date_err))
             .transpose()?;
-        let resources = config
-            .resources()
-            .unwrap_or_default()
-            .iter()
-            .map(|js_string| js_string.as_string())
-            .map(|s: Option<String>| match s {
-                Some(string) => string
-                    .parse()
-                    .map_err(|e| format!("unable to parse resource as uri: {}", e).into()),
-                None => Err("error converting UTF-16 to UTF-8".into()),
+        let mut capability = Cow::Borrowed(&self.capability);
+        let mut resources = Vec::with_capacity(params.resources.len());
+        for resource in &params.resources {
+            if params.merge_resources && is_recap_uri(resource) {
+                let recap = decode_recap_uri(resource)?;
+                capability = Cow::Owned(merge_capabilities(&capability, &recap)?);
+            } else {
+                resources.push(
+                    resource
+                        .parse::<UriString>()
+                        .map_err(|e| format!("unable to parse resource as uri: {}", e))?,
+                );
+            }
+        }
+        let version = params
+            .version
+            .map(|v| {
+                v.parse::<SiweVersion>()
+                    .map_err(|e| format!("unsupported siwe version '{}': {}", v, e))
             })
-            .collect::<Result<Vec<_>, JsValue>>()?;
+            .transpose()?
+            .unwrap_or(SiweVersion::V1);
+        let statement = match params.statement {
+            None if params.generate_statement => {
+                Self::capability_statement(&capability, params.address_label.as_deref())
+            }
+            statement => statement,
+        };
         let message = Message {
             scheme: None,
             domain,
             address,
-            statement: config.statement(),
+            statement,
             uri,
-            version: SiweVersion::V1,
-            chain_id: config.chainId() as u64,
+            version,
+            chain_id: params.chain_id as u64,
             nonce,
             issued_at,
             expiration_time,
             not_before,
-            request_id: config.requestId(),
+            request_id: params.request_id,
             resources,
         };
 
-        let siwe = self
-            .capability
+        let message = capability
             .build_message(message)
             .map_err(|build_error| format!("unable to build siwe message: {}", build_error))?;
-        Ok(siwe.to_string())
+        if let Some(recap) = message
+            .resources
+            .last()
+            .filter(|resource| is_recap_uri(resource.as_str()))
+        {
+            if recap.as_str().len() > MAX_RECAP_BYTES {
+                return Err(format!(
+                    "recap of {} bytes exceeds the limit of {} bytes",
+                    recap.as_str().len(),
+                    MAX_RECAP_BYTES
+                ));
+            }
+        }
+        Ok(message)
     }
 
     /// Add actions for a specific target to a capability.
+    ///
+    /// The target may be any resource URI, so this also adds fully-qualified recap
+    /// abilities (e.g. `kv/read`) for an explicit resource.
     pub fn add_targeted_actions(&mut self, target: String, actions: Vec<JsString>) -> bool {
         let actions: Vec<String> = if let Some(actions) = actions
             .iter()
@@ -141,14 +571,124 @@ impl SessionManager {
             return false;
         };
 
-        // Create a properly formatted resource URI
+        self.add_actions(&target, &actions)
+    }
+
+    /// Add actions of a namespace for a specific target, attaching `fields` to each
+    /// action as nota-bene constraints in the recap.
+    pub fn add_targeted_actions_with_fields(
+        &mut self,
+        namespace: String,
+        target: String,
+        actions: Vec<JsString>,
+        fields: ExtraFields,
+    ) -> bool {
+        let actions: Vec<String> = if let Some(actions) = actions
+            .iter()
+            .map(|js_string| js_string.as_string())
+            .collect()
+        {
+            actions
+        } else {
+            string_conversion_error();
+            return false;
+        };
+        let fields: BTreeMap<String, Value> =
+            match serde_wasm_bindgen::from_value(JsValue::from(fields)) {
+                Ok(fields) => fields,
+                Err(e) => {
+                    log_error(&format!("Invalid extra fields: {}", e));
+                    return false;
+                }
+            };
+
+        let actions: Vec<String> = actions
+            .iter()
+            .map(|action| format!("{}/{}", namespace, action))
+            .collect();
+        self.add_actions_with_fields(&target, &actions, fields)
+    }
+
+    /// Add an action of a namespace for a specific target with its own validity window,
+    /// recorded as `nbf`/`exp` (seconds since the Unix epoch) nota-bene fields.
+    pub fn add_targeted_action_with_expiry(
+        &mut self,
+        namespace: String,
+        target: String,
+        action: String,
+        not_before: Option<u64>,
+        expiration: Option<u64>,
+    ) -> bool {
+        let mut fields = BTreeMap::new();
+        if let Some(not_before) = not_before {
+            fields.insert("nbf".to_string(), Value::from(not_before));
+        }
+        if let Some(expiration) = expiration {
+            fields.insert("exp".to_string(), Value::from(expiration));
+        }
+        self.add_actions_with_fields(&target, &[format!("{}/{}", namespace, action)], fields)
+    }
+
+    /// Add actions for a target, returning `false` without modifying the capability if the
+    /// target or any of the actions fail to parse.
+    pub(crate) fn add_actions(&mut self, target: &str, actions: &[String]) -> bool {
+        self.add_actions_with_fields(target, actions, BTreeMap::new())
+    }
+
+    /// Like [`SessionManager::add_actions`], attaching `fields` to each action unless empty.
+    pub(crate) fn add_actions_with_fields(
+        &mut self,
+        target: &str,
+        actions: &[String],
+        fields: BTreeMap<String, Value>,
+    ) -> bool {
+        let target = match target.parse::<UriString>() {
+            Ok(target) => target,
+            Err(e) => {
+                log_error(&format!("Invalid action target '{}': {}", target, e));
+                return false;
+            }
+        };
+        let abilities = match actions
+            .iter()
+            .map(|action| {
+                action
+                    .parse::<Ability>()
+                    .map_err(|e| format!("Invalid action '{}': {}", action, e))
+            })
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(abilities) => abilities,
+            Err(e) => {
+                log_error(&e);
+                return false;
+            }
+        };
+
+        let existing = self.capability.abilities().get(&target);
+        let added = abilities
+            .iter()
+            .filter(|ability| existing.is_none_or(|existing| !existing.contains_key(*ability)))
+            .collect::<BTreeSet<_>>()
+            .len();
+        if self.ability_count() + added > self.max_abilities {
+            log_error(&format!(
+                "Unable to add targeted actions: the capability is limited to {} actions",
+                self.max_abilities
+            ));
+            return false;
+        }
 
-        for action in actions {
-            if let Err(e) = self.capability.with_action_convert(
-                target.parse::<UriString>().unwrap(),
-                action.parse::<Ability>().unwrap(),
-                Vec::<std::collections::BTreeMap<String, Value>>::new(),
-            ) {
+        let nota_bene = if fields.is_empty() {
+            vec![]
+        } else {
+            vec![fields]
+        };
+        for ability in abilities {
+            if let Err(e) =
+                self.capability
+                    .with_action_convert(target.clone(), ability, nota_bene.clone())
+            {
                 log_error(&format!("Failed to add targeted action: {}", e));
                 return false;
             }
@@ -156,22 +696,36 @@ impl SessionManager {
         true
     }
 
-    pub fn create_session_key(&mut self, key_id: Option<String>) -> Result<String, String> {
+    pub fn create_session_key(&mut self, key_id: Option<String>) -> Result<String, SessionError> {
+        self.create_session_key_with_algorithm(key_id, KeyAlgorithm::default())
+    }
+
+    /// Create a new session key using the given algorithm.
+    pub fn create_session_key_with_algorithm(
+        &mut self,
+        key_id: Option<String>,
+        algorithm: KeyAlgorithm,
+    ) -> Result<String, SessionError> {
         let key_id = key_id.unwrap_or(DEFAULT_KEY_ID.to_string());
         if self.sessions.contains_key(&key_id) {
-            return Err(format!("key already exists: {}", key_id));
+            return Err(SessionError::KeyExists(format!(
+                "key already exists: {}",
+                key_id
+            )));
         }
-        let mut new_key: JWK = JWK::generate_ed25519()
-            .map_err(|error| format!("failed to generate session key: {}", error))?;
+        let mut new_key: JWK = algorithm.generate().map_err(SessionError::Crypto)?;
 
         // add key_id to jwk
         new_key.key_id = Some(key_id.clone());
 
+        let seq = self.next_sequence();
         self.sessions.insert(
             key_id.clone(),
             SessionInfo {
                 key: Some(new_key),
                 session: None,
+                siwe: None,
+                seq,
             },
         );
         Ok(key_id)
@@ -182,40 +736,131 @@ impl SessionManager {
         mut key: JWK,
         key_id: Option<String>,
         override_key_id: bool,
-    ) -> Result<String, String> {
+    ) -> Result<String, SessionError> {
         let key_id = key_id.unwrap_or(DEFAULT_KEY_ID.to_string());
+        // a session key has to sign, so a public-only JWK is of no use
+        if !has_private_key(&key) {
+            return Err(SessionError::InvalidJwk(format!(
+                "session key {} has no private key",
+                key_id
+            )));
+        }
         if self.sessions.contains_key(&key_id) && !override_key_id {
-            return Err(format!("key already exists: {}", key_id));
+            return Err(SessionError::KeyExists(format!(
+                "key already exists: {}",
+                key_id
+            )));
         }
 
         // add "kid" to jwk
         key.key_id = Some(key_id.clone());
 
+        // an overridden key keeps its place in the creation order
+        let seq = match self.sessions.get(&key_id) {
+            Some(session_info) => {
+                log_warn(&format!("Replacing session key: {}", key_id));
+                session_info.seq
+            }
+            None => self.next_sequence(),
+        };
         self.sessions.insert(
             key_id.clone(),
             SessionInfo {
                 key: Some(key),
                 session: None,
+                siwe: None,
+                seq,
             },
         );
         Ok(key_id)
     }
 
+    /// Import an Ed25519 session key derived deterministically from a 32-byte seed.
+    pub fn import_session_key_from_seed(
+        &mut self,
+        seed_base64: String,
+        key_id: Option<String>,
+    ) -> Result<String, String> {
+        let seed: [u8; 32] = STANDARD
+            .decode(seed_base64.trim())
+            .map_err(|e| format!("invalid base64 encoding for seed: {}", e))?
+            .try_into()
+            .map_err(|seed: Vec<u8>| format!("seed must be 32 bytes, got {}", seed.len()))?;
+        let signing_key = ed25519_dalek::SigningKey::from_bytes(&seed);
+
+        let key = JWK::from(Params::OKP(OctetParams {
+            curve: "Ed25519".to_string(),
+            public_key: Base64urlUInt(signing_key.verifying_key().to_bytes().to_vec()),
+            private_key: Some(Base64urlUInt(seed.to_vec())),
+        }));
+        Ok(self.import_session_key(key, key_id, false)?)
+    }
+
+    /// Whether a session key with this key_id exists.
+    pub fn has_session_key(&self, key_id: &str) -> bool {
+        self.sessions.contains_key(key_id)
+    }
+
     pub fn list_session_keys(&self) -> Vec<String> {
         let keys = self.sessions.keys().cloned().collect();
         keys
     }
 
+    /// Replace the session key key_id with a fresh key of the same algorithm.
+    ///
+    /// The stored session of the old key is dropped, while the capability is kept so the
+    /// next `build` grants it to the new key.
+    pub fn rotate_session_key(&mut self, key_id: Option<String>) -> Result<String, String> {
+        let key_id = key_id.unwrap_or(DEFAULT_KEY_ID.to_string());
+        let old_key = self.get_private_key(Some(key_id.clone()))?;
+        let algorithm = KeyAlgorithm::of(&old_key)
+            .ok_or_else(|| format!("unable to rotate key {}: unsupported key type", key_id))?;
+        let mut new_key = algorithm.generate()?;
+
+        // add key_id to jwk
+        new_key.key_id = Some(key_id.clone());
+
+        let session_info = self
+            .sessions
+            .get_mut(&key_id)
+            .ok_or(format!("key not found: {}", key_id))?;
+        session_info.key = Some(new_key);
+        if session_info.session.take().is_some() {
+            log_info(&format!("Dropped the session of rotated key: {}", key_id));
+        }
+        session_info.siwe = None;
+        Ok(key_id)
+    }
+
+    /// List the session key ids in the order the keys were created or imported.
+    pub fn list_session_keys_sorted(&self) -> Vec<String> {
+        let mut keys: Vec<(&String, &SessionInfo)> = self.sessions.iter().collect();
+        keys.sort_by(|(a_id, a), (b_id, b)| a.seq.cmp(&b.seq).then_with(|| a_id.cmp(b_id)));
+        keys.into_iter().map(|(key_id, _)| key_id.clone()).collect()
+    }
+
+    fn next_sequence(&mut self) -> u64 {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        seq
+    }
+
     pub fn rename_session_key_id(
         &mut self,
         old_key_id: String,
         new_key_id: String,
-    ) -> Result<(), String> {
+    ) -> Result<(), SessionError> {
         if !self.sessions.contains_key(&old_key_id) {
-            return Err(format!("Key {} does not exist.", old_key_id));
+            return Err(SessionError::KeyNotFound(format!(
+                "Key {} does not exist.",
+                old_key_id
+            )));
         }
         if self.sessions.contains_key(&new_key_id) {
-            return Err(format!("Key {} already exists.", new_key_id));
+            return Err(SessionError::KeyExists(format!(
+                "Key {} already exists.",
+                new_key_id
+            )));
         }
         if let Some(session_info) = self.sessions.remove(&old_key_id) {
             self.sessions.insert(new_key_id, session_info);
@@ -223,7 +868,29 @@ impl SessionManager {
         Ok(())
     }
 
+    /// Change the DID method of the identity returned by [`SessionManager::get_did`].
+    pub fn set_did_method(&mut self, method: DidMethodKind) {
+        self.did_method = method;
+    }
+
+    /// Get the DID of the session, as used by JS callers.
+    ///
+    /// With the default `key` method this is the verification method URI of the session
+    /// key, the same as [`SessionManager::get_did_vm`].
     pub fn get_did(&self, key_id: Option<String>) -> Result<String, String> {
+        // the DID identifies the holder of the session key, so the key must exist
+        self.get_private_key(key_id.clone())?;
+        match &self.did_method {
+            DidMethodKind::Key => self.get_did_vm(key_id),
+            DidMethodKind::Pkh { chain_id, address } => self.get_did_pkh(*chain_id, address),
+        }
+    }
+
+    /// Get the verification method URI (`did:key:z6Mk...#z6Mk...`) of the session key.
+    ///
+    /// A `did:key` document has a single verification method, identified by the
+    /// multibase-encoded key as the fragment of the DID.
+    pub fn get_did_vm(&self, key_id: Option<String>) -> Result<String, String> {
         let did = DIDKey::generate(&self.get_private_key(key_id)?)
             .map_err(|e| format!("unable to generate the DID of the session key: {e}"))?;
         // Create a proper DID URL with fragment: did:key:z6Mk...#z6Mk...
@@ -235,7 +902,64 @@ impl SessionManager {
         Ok(format!("{}#{}", did_str, fragment))
     }
 
+    /// Get a `did:pkh:eip155` identity for an Ethereum account.
+    pub fn get_did_pkh(&self, chain_id: u64, address: &str) -> Result<String, String> {
+        Ok(format!(
+            "did:pkh:eip155:{}:0x{}",
+            chain_id,
+            pkh_address_hex(address)?
+        ))
+    }
+
+    /// Get the algorithm of a session key: "Ed25519", "secp256k1" or "P-256".
+    pub fn key_algorithm(&self, key_id: Option<String>) -> Result<String, String> {
+        let key = self.get_private_key(key_id)?;
+        KeyAlgorithm::of(&key)
+            .map(|algorithm| algorithm.to_string())
+            .ok_or_else(|| "unsupported session key type".to_string())
+    }
+
+    /// Get the 33-byte compressed SEC1 encoding of a secp256k1 session key as `0x`-prefixed hex.
+    pub fn compressed_pubkey_hex(&self, key_id: Option<String>) -> Result<String, String> {
+        let key = self.get_private_key(key_id)?;
+        let (x, y) = match &key.params {
+            Params::EC(params) if params.curve.as_deref() == Some("secp256k1") => {
+                match (&params.x_coordinate, &params.y_coordinate) {
+                    (Some(x), Some(y)) => (&x.0, &y.0),
+                    _ => return Err("secp256k1 key is missing its public coordinates".to_string()),
+                }
+            }
+            _ => return Err("session key is not a secp256k1 key".to_string()),
+        };
+        if x.len() > 32 || y.is_empty() || y.len() > 32 {
+            return Err("invalid secp256k1 public key coordinates".to_string());
+        }
+
+        let mut compressed = [0u8; 33];
+        compressed[0] = if y[y.len() - 1] & 1 == 0 { 0x02 } else { 0x03 };
+        compressed[33 - x.len()..].copy_from_slice(x);
+        Ok(format!("0x{}", hex::encode(compressed)))
+    }
+
+    /// Sign `payload` as a compact JWS with the session key, using the algorithm of its
+    /// key type (`EdDSA` for Ed25519, `ES256K` for secp256k1, `ES256` for P-256).
+    pub fn sign_jws(&self, payload: String, key_id: Option<String>) -> Result<String, String> {
+        let key = self.get_private_key(key_id)?;
+        let algorithm = key
+            .get_algorithm()
+            .ok_or("unable to determine the signing algorithm of the session key")?;
+        jws::encode_sign(algorithm, &payload, &key)
+            .map_err(|e| format!("failed to sign JWS: {}", e))
+    }
+
     fn get_private_key(&self, key_id: Option<String>) -> Result<JWK, String> {
+        if self
+            .sessions
+            .values()
+            .all(|session_info| session_info.key.is_none())
+        {
+            return Err("no keys available: create or import a session key first".to_string());
+        }
         let key_id = key_id.unwrap_or(DEFAULT_KEY_ID.to_string());
         let session_info = self
             .sessions
@@ -249,7 +973,14 @@ impl SessionManager {
     }
 
     pub fn jwk(&self, key_id: Option<String>) -> Option<String> {
-        match serde_json::to_string(&self.get_private_key(key_id).unwrap()) {
+        let key = match self.get_private_key(key_id) {
+            Ok(key) => key,
+            Err(e) => {
+                log_error(&e);
+                return None;
+            }
+        };
+        match serde_json::to_string(&key) {
             Ok(s) => Some(s),
             Err(e) => {
                 log_error(&e.to_string());
@@ -258,21 +989,297 @@ impl SessionManager {
         }
     }
 
-    pub fn update_session(
-        &mut self,
-        session: Session,
-        key_id: Option<String>,
-    ) -> Result<(), String> {
-        let final_key_id = key_id
-            .or(session.jwk.key_id.clone())
-            .ok_or("No key_id provided")?;
-
-        let session_info = self.sessions.entry(final_key_id).or_default();
-
-        session_info.session = Some(session);
+    /// Get the key ID, DID, public JWK, algorithm and session status of a session key.
+    pub fn key_summary(&self, key_id: Option<String>) -> Result<JsValue, JsValue> {
+        let summary = self.summarize_key(key_id)?;
+        summary
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(JsValue::from)
+    }
+
+    pub(crate) fn summarize_key(&self, key_id: Option<String>) -> Result<KeySummary, String> {
+        let key_id = key_id.unwrap_or(DEFAULT_KEY_ID.to_string());
+        let key = self.get_private_key(Some(key_id.clone()))?;
+        let has_session = self
+            .sessions
+            .get(&key_id)
+            .is_some_and(|session_info| session_info.session.is_some());
+        Ok(KeySummary {
+            did: self.get_did(Some(key_id.clone()))?,
+            public_jwk: key.to_public(),
+            alg: key.get_algorithm(),
+            has_session,
+            key_id,
+        })
+    }
+
+    /// Get the public half of the session key, with all private parameters removed.
+    pub fn public_jwk(&self, key_id: Option<String>) -> Option<String> {
+        let key = match self.get_private_key(key_id) {
+            Ok(key) => key.to_public(),
+            Err(e) => {
+                log_error(&e);
+                return None;
+            }
+        };
+        match serde_json::to_string(&key) {
+            Ok(s) => Some(s),
+            Err(e) => {
+                log_error(&e.to_string());
+                None
+            }
+        }
+    }
+
+    /// Drop every stored session while keeping the session keys (and so their DIDs).
+    pub fn clear_sessions(&mut self) {
+        for session_info in self.sessions.values_mut() {
+            session_info.session = None;
+            session_info.siwe = None;
+        }
+    }
+
+    /// Serialize all session keys, sessions and the capability to JSON.
+    ///
+    /// The output contains the private session keys, so it must be stored as securely
+    /// as the keys themselves.
+    pub fn export_state(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|e| format!("failed to serialize state: {}", e))
+    }
+
+    /// Restore a SessionManager from the output of [`SessionManager::export_state`].
+    pub fn import_state(json: String) -> Result<SessionManager, String> {
+        serde_json::from_str(&json).map_err(|e| format!("failed to deserialize state: {}", e))
+    }
+
+    /// Store a session without its SIWE message.
+    ///
+    /// Any SIWE message stored for a previous session of the key is dropped, so
+    /// [`SessionManager::is_session_expired`] fails for this session.
+    pub fn update_session(
+        &mut self,
+        session: Session,
+        key_id: Option<String>,
+    ) -> Result<(), String> {
+        self.update_session_with_siwe(session, None, key_id)
+    }
+
+    /// Store a session together with the SIWE message it was created from.
+    pub fn update_session_with_siwe(
+        &mut self,
+        session: Session,
+        siwe: Option<String>,
+        key_id: Option<String>,
+    ) -> Result<(), String> {
+        let final_key_id = key_id
+            .or(session.jwk.key_id.clone())
+            .ok_or("No key_id provided")?;
+
+        let next_seq = &mut self.next_seq;
+        let session_info = self.sessions.entry(final_key_id).or_insert_with(|| {
+            let seq = *next_seq;
+            *next_seq += 1;
+            SessionInfo {
+                seq,
+                ..Default::default()
+            }
+        });
+
+        session_info.session = Some(session);
+        session_info.siwe = siwe.filter(|siwe| !siwe.is_empty());
 
         Ok(())
     }
+
+    /// Whether the session stored for key_id has expired at `now_unix_secs`.
+    ///
+    /// The expiration is read from the SIWE message of the session; a message
+    /// without an expiration time never expires.
+    pub fn is_session_expired(
+        &self,
+        key_id: Option<String>,
+        now_unix_secs: u64,
+    ) -> Result<bool, String> {
+        let key_id = key_id.unwrap_or(DEFAULT_KEY_ID.to_string());
+        let session_info = self
+            .sessions
+            .get(&key_id)
+            .filter(|session_info| session_info.session.is_some())
+            .ok_or_else(|| format!("no session stored for key: {}", key_id))?;
+        let siwe = session_info
+            .siwe
+            .as_ref()
+            .ok_or_else(|| format!("no SIWE message stored for the session of key: {}", key_id))?;
+        let message =
+            Message::from_str(siwe).map_err(|e| format!("unable to parse siwe message: {}", e))?;
+
+        match message.expiration_time {
+            Some(expiration_time) => {
+                let expiration_time = OffsetDateTime::parse(&expiration_time.to_string(), &Rfc3339)
+                    .map_err(|e| format!("unable to parse timestamp from string: {}", e))?;
+                let now = i64::try_from(now_unix_secs).unwrap_or(i64::MAX);
+                Ok(now >= expiration_time.unix_timestamp())
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+/// Human-readable name of a well-known EIP-155 chain id, for display purposes.
+pub fn supported_chain_name(chain_id: u64) -> Option<String> {
+    let name = match chain_id {
+        1 => "Ethereum",
+        10 => "OP Mainnet",
+        56 => "BNB Smart Chain",
+        100 => "Gnosis",
+        137 => "Polygon",
+        324 => "zkSync Era",
+        8453 => "Base",
+        17000 => "Holesky",
+        42161 => "Arbitrum One",
+        42220 => "Celo",
+        43114 => "Avalanche C-Chain",
+        59144 => "Linea",
+        84532 => "Base Sepolia",
+        11155111 => "Sepolia",
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+/// Whether a JWK carries the private parameters needed to sign with it.
+///
+/// Only the key types a session key can be generated with are checked.
+pub(crate) fn has_private_key(key: &JWK) -> bool {
+    match &key.params {
+        Params::OKP(params) => params.private_key.is_some(),
+        Params::EC(params) => params.ecc_private_key.is_some(),
+        _ => true,
+    }
+}
+
+/// The actions of a capability, keyed by target.
+fn abilities_by_target(capability: &Capability<Value>) -> BTreeMap<String, Vec<String>> {
+    capability
+        .abilities()
+        .iter()
+        .map(|(target, abilities)| {
+            (
+                target.to_string(),
+                abilities
+                    .keys()
+                    .map(|ability| ability.to_string())
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+/// Minimum length of a SIWE nonce, per EIP-4361.
+const MIN_NONCE_LENGTH: usize = 8;
+
+/// Check the fields of a SIWE config without building a message, reporting every
+/// problem found rather than only the first.
+pub fn validate_config(config: &SiweConfig) -> Result<(), Vec<String>> {
+    let params = SiweParams::try_from(config).map_err(|e| vec![e])?;
+    validate_params(&params)
+}
+
+pub(crate) fn validate_params(params: &SiweParams) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+    if let Err(e) = validate_address(&params.address) {
+        errors.push(e);
+    }
+    if params.chain_id == 0 {
+        errors.push("invalid chain id: chain id must be non-zero".to_string());
+    }
+    if let Some(nonce) = &params.nonce {
+        if nonce.len() < MIN_NONCE_LENGTH || !nonce.chars().all(|c| c.is_ascii_alphanumeric()) {
+            errors.push(format!(
+                "nonce must be at least {} alphanumeric characters",
+                MIN_NONCE_LENGTH
+            ));
+        }
+    }
+    let timestamps = [
+        ("issuedAt", Some(&params.issued_at)),
+        ("expirationTime", params.expiration_time.as_ref()),
+        ("notBefore", params.not_before.as_ref()),
+    ];
+    for (field, timestamp) in timestamps {
+        if let Some(timestamp) = timestamp {
+            if let Err(e) = OffsetDateTime::parse(timestamp, &Rfc3339) {
+                errors.push(format!("unable to parse {} as a timestamp: {}", field, e));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// URI schemes accepted for the SIWE `uri` of a built message.
+const CUSTOM_URI_SCHEMES: [&str; 3] = ["https", "http", "did"];
+
+/// Parse a caller-provided SIWE `uri`, which must use one of [`CUSTOM_URI_SCHEMES`].
+fn parse_custom_uri(custom_uri: &str) -> Result<UriString, String> {
+    let uri = UriString::from_str(custom_uri)
+        .map_err(|e| format!("invalid custom URI '{}': {}", custom_uri, e))?;
+    let scheme = uri.scheme_str();
+    if !CUSTOM_URI_SCHEMES
+        .iter()
+        .any(|allowed| scheme.eq_ignore_ascii_case(allowed))
+    {
+        return Err(format!(
+            "invalid custom URI '{}': scheme must be one of {}",
+            custom_uri,
+            CUSTOM_URI_SCHEMES.join(", ")
+        ));
+    }
+    Ok(uri)
+}
+
+/// Strip the `0x` prefix of a `did:pkh` account address, checking that it is 20 hex-encoded bytes.
+fn pkh_address_hex(address: &str) -> Result<&str, String> {
+    let hex_address = address.strip_prefix("0x").unwrap_or(address);
+    match hex::decode(hex_address) {
+        Ok(bytes) if bytes.len() == 20 => Ok(hex_address),
+        _ => Err(format!(
+            "invalid Ethereum address '{}': expected 20 hex-encoded bytes",
+            address
+        )),
+    }
+}
+
+/// Decode an Ethereum address, enforcing the EIP-55 checksum when it is mixed-case.
+fn validate_address(address: &str) -> Result<[u8; 20], String> {
+    let hex_address = address.strip_prefix("0x").unwrap_or(address);
+    let bytes: [u8; 20] = hex::decode(hex_address)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| format!("address '{}' must be 20 hex-encoded bytes", address))?;
+
+    let has_lowercase = hex_address.chars().any(|c| c.is_ascii_lowercase());
+    let has_uppercase = hex_address.chars().any(|c| c.is_ascii_uppercase());
+    if has_lowercase && has_uppercase && eip55(&bytes)[2..] != *hex_address {
+        return Err("address failed EIP-55 checksum".to_string());
+    }
+    Ok(bytes)
+}
+
+/// Add `secs` to an ISO 8601 timestamp, returning the result as an ISO 8601 string.
+fn offset_timestamp(timestamp: &str, secs: u64) -> Result<String, String> {
+    let start = OffsetDateTime::parse(timestamp, &Rfc3339)
+        .map_err(|e| format!("unable to parse timestamp from string: {}", e))?;
+    let end = i64::try_from(secs)
+        .ok()
+        .and_then(|secs| start.checked_add(Duration::seconds(secs)))
+        .ok_or_else(|| format!("validity duration of {} seconds is out of range", secs))?;
+    end.format(&Rfc3339)
+        .map_err(|e| format!("unable to format timestamp: {}", e))
 }
 
 fn string_conversion_error() {
@@ -282,7 +1289,7 @@ fn string_conversion_error() {
 #[cfg(test)]
 pub mod test {
     use super::*;
-    use ssi::jwk::JWK;
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
     use std::collections::HashSet;
 
     #[tokio::test]
@@ -294,6 +1301,33 @@ pub mod test {
         assert_eq!(keys.len(), 1);
     }
 
+    #[tokio::test]
+    async fn test_empty_session_manager() {
+        let manager = SessionManager::empty();
+        assert!(manager.list_session_keys().is_empty());
+        assert!(!manager.has_capabilities());
+
+        let error = manager.get_did(None).unwrap_err();
+        assert!(error.starts_with("no keys available"));
+        let error = manager
+            .build_message(test_params(), None, None)
+            .unwrap_err();
+        assert!(error.starts_with("no keys available"));
+    }
+
+    #[tokio::test]
+    async fn test_empty_session_manager_import_and_build() {
+        let mut manager = SessionManager::empty();
+        manager
+            .import_session_key_from_seed(TEST_SEED.to_string(), None)
+            .unwrap();
+        add_test_action(&mut manager, "https://example.com/", "tinycloud.kv/get");
+
+        assert_eq!(manager.list_session_keys(), vec!["default".to_string()]);
+        let message = manager.build_message(test_params(), None, None).unwrap();
+        assert_eq!(message.uri.as_str(), manager.get_did(None).unwrap());
+    }
+
     #[tokio::test]
     async fn test_create_session_key() {
         let mut manager = SessionManager::new().unwrap();
@@ -302,12 +1336,72 @@ pub mod test {
         assert!(manager.sessions.contains_key("custom_key"));
     }
 
+    #[tokio::test]
+    async fn test_create_p256_session_key() {
+        let mut manager = SessionManager::new().unwrap();
+        manager
+            .create_session_key_with_algorithm(Some("passkey".to_string()), KeyAlgorithm::P256)
+            .unwrap();
+
+        let jwk: Value =
+            serde_json::from_str(&manager.jwk(Some("passkey".to_string())).unwrap()).unwrap();
+        assert_eq!(jwk["crv"], "P-256");
+        let did = manager.get_did(Some("passkey".to_string())).unwrap();
+        assert!(did.starts_with("did:key:zDn"));
+    }
+
+    #[tokio::test]
+    async fn test_parse_key_algorithm() {
+        assert_eq!("P-256".parse::<KeyAlgorithm>(), Ok(KeyAlgorithm::P256));
+        assert_eq!(
+            "secp256k1".parse::<KeyAlgorithm>(),
+            Ok(KeyAlgorithm::Secp256k1)
+        );
+        assert_eq!(
+            "RSA".parse::<KeyAlgorithm>().unwrap_err().code(),
+            "ParseError"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_key_algorithm() {
+        let mut manager = SessionManager::new().unwrap();
+        assert_eq!(manager.key_algorithm(None), Ok("Ed25519".to_string()));
+
+        manager
+            .import_session_key(
+                JWK::generate_secp256k1(),
+                Some("imported".to_string()),
+                false,
+            )
+            .unwrap();
+        assert_eq!(
+            manager.key_algorithm(Some("imported".to_string())),
+            Ok("secp256k1".to_string())
+        );
+        assert!(manager.key_algorithm(Some("missing".to_string())).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_key_algorithm_round_trip() {
+        for algorithm in [
+            KeyAlgorithm::Ed25519,
+            KeyAlgorithm::Secp256k1,
+            KeyAlgorithm::P256,
+        ] {
+            assert_eq!(algorithm.to_string().parse::<KeyAlgorithm>(), Ok(algorithm));
+        }
+    }
+
     #[tokio::test]
     async fn test_create_duplicate_session_key() {
         let mut manager = SessionManager::new().unwrap();
         let _ = manager.create_session_key(Some("custom_key".to_string()));
         let result = manager.create_session_key(Some("custom_key".to_string()));
         assert!(result.is_err());
+        let error = result.unwrap_err();
+        assert_eq!(error.code(), "KeyExists");
+        assert_eq!(error.message(), "key already exists: custom_key");
     }
 
     #[tokio::test]
@@ -319,6 +1413,64 @@ pub mod test {
         assert!(manager.sessions.contains_key("imported_key"));
     }
 
+    #[tokio::test]
+    async fn test_import_public_session_key() {
+        let mut manager = SessionManager::new().unwrap();
+        for key in [JWK::generate_ed25519().unwrap(), JWK::generate_p256()] {
+            let result =
+                manager.import_session_key(key.to_public(), Some("public".to_string()), false);
+            assert_eq!(
+                result,
+                Err(SessionError::InvalidJwk(
+                    "session key public has no private key".to_string()
+                ))
+            );
+        }
+        assert!(!manager.sessions.contains_key("public"));
+    }
+
+    /// Private key of test 1 from RFC 8032, section 7.1.
+    const TEST_SEED: &str = "nWGxne/9WmC6hEr0kuwsxERJxWl7MmkZcDusAxyuf2A=";
+
+    #[tokio::test]
+    async fn test_import_session_key_from_seed() {
+        let mut manager = SessionManager::new().unwrap();
+        let key_id = manager
+            .import_session_key_from_seed(TEST_SEED.to_string(), Some("seeded".to_string()))
+            .unwrap();
+        assert_eq!(key_id, "seeded");
+
+        let jwk: Value =
+            serde_json::from_str(&manager.jwk(Some("seeded".to_string())).unwrap()).unwrap();
+        assert_eq!(jwk["crv"], "Ed25519");
+        // Public key of test 1 from RFC 8032
+        assert_eq!(jwk["x"], "11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo");
+    }
+
+    #[tokio::test]
+    async fn test_seed_yields_same_did() {
+        let mut first = SessionManager::new().unwrap();
+        let mut second = SessionManager::new().unwrap();
+        for manager in [&mut first, &mut second] {
+            manager
+                .import_session_key_from_seed(TEST_SEED.to_string(), Some("seeded".to_string()))
+                .unwrap();
+        }
+        assert_eq!(
+            first.get_did(Some("seeded".to_string())).unwrap(),
+            second.get_did(Some("seeded".to_string())).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_seed_wrong_length() {
+        let mut manager = SessionManager::new().unwrap();
+        let result = manager
+            .import_session_key_from_seed(STANDARD.encode([1u8; 16]), Some("seeded".to_string()));
+        assert!(result.is_err());
+        assert!(!manager.sessions.contains_key("seeded"));
+    }
+
     #[tokio::test]
     async fn test_list_session_keys() {
         let mut manager = SessionManager::new().unwrap();
@@ -330,6 +1482,84 @@ pub mod test {
         assert_eq!(keys.into_iter().collect::<HashSet<_>>(), key_set);
     }
 
+    #[tokio::test]
+    async fn test_list_session_keys_sorted() {
+        let mut manager = SessionManager::new().unwrap();
+        for key_id in ["zeta", "alpha", "mid"] {
+            manager
+                .create_session_key(Some(key_id.to_string()))
+                .unwrap();
+        }
+        let expected = vec!["default", "zeta", "alpha", "mid"];
+        assert_eq!(manager.list_session_keys_sorted(), expected);
+        assert_eq!(manager.list_session_keys_sorted(), expected);
+
+        manager
+            .rename_session_key_id("zeta".to_string(), "omega".to_string())
+            .unwrap();
+        manager
+            .import_session_key(
+                JWK::generate_ed25519().unwrap(),
+                Some("alpha".to_string()),
+                true,
+            )
+            .unwrap();
+        assert_eq!(
+            manager.list_session_keys_sorted(),
+            vec!["default", "omega", "alpha", "mid"]
+        );
+
+        let restored = SessionManager::import_state(manager.export_state().unwrap()).unwrap();
+        assert_eq!(
+            restored.list_session_keys_sorted(),
+            manager.list_session_keys_sorted()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rotate_session_key() {
+        let mut manager = SessionManager::new().unwrap();
+        add_test_action(&mut manager, "https://example.com/", "tinycloud.kv/get");
+        let session = test_session(&manager, None);
+        manager.update_session(session, None).unwrap();
+        let did = manager.get_did(None).unwrap();
+        let abilities = manager.list_abilities();
+
+        assert_eq!(manager.rotate_session_key(None), Ok("default".to_string()));
+
+        let rotated_did = manager.get_did(None).unwrap();
+        assert_ne!(rotated_did, did);
+        assert!(rotated_did.starts_with("did:key:z6Mk"));
+        assert_eq!(manager.list_abilities(), abilities);
+        assert!(manager.sessions["default"].session.is_none());
+        let message = manager.build_message(test_params(), None, None).unwrap();
+        assert_eq!(message.uri.as_str(), rotated_did);
+    }
+
+    #[tokio::test]
+    async fn test_rotate_session_key_keeps_algorithm() {
+        let mut manager = SessionManager::new().unwrap();
+        manager
+            .create_session_key_with_algorithm(Some("passkey".to_string()), KeyAlgorithm::P256)
+            .unwrap();
+        let key_id = Some("passkey".to_string());
+        let did = manager.get_did(key_id.clone()).unwrap();
+
+        manager.rotate_session_key(key_id.clone()).unwrap();
+
+        let rotated_did = manager.get_did(key_id).unwrap();
+        assert_ne!(rotated_did, did);
+        assert!(rotated_did.starts_with("did:key:zDn"));
+    }
+
+    #[tokio::test]
+    async fn test_rotate_missing_session_key() {
+        let mut manager = SessionManager::new().unwrap();
+        assert!(manager
+            .rotate_session_key(Some("missing".to_string()))
+            .is_err());
+    }
+
     #[tokio::test]
     async fn test_rename_session_key_id() {
         let mut manager = SessionManager::new().unwrap();
@@ -346,6 +1576,51 @@ pub mod test {
         let result =
             manager.rename_session_key_id("nonexistent_key".to_string(), "new_key".to_string());
         assert!(result.is_err());
+        assert_eq!(result.unwrap_err().code(), "KeyNotFound");
+    }
+
+    #[tokio::test]
+    async fn test_rename_to_existing_session_key_id() {
+        let mut manager = SessionManager::new().unwrap();
+        manager
+            .create_session_key(Some("custom_key".to_string()))
+            .unwrap();
+        let result = manager.rename_session_key_id("default".to_string(), "custom_key".to_string());
+        assert_eq!(
+            result,
+            Err(SessionError::KeyExists(
+                "Key custom_key already exists.".to_string()
+            ))
+        );
+        assert!(manager.sessions.contains_key("default"));
+    }
+
+    #[tokio::test]
+    async fn test_session_error_codes() {
+        let errors = [
+            (SessionError::KeyExists("a".to_string()), "KeyExists"),
+            (SessionError::KeyNotFound("b".to_string()), "KeyNotFound"),
+            (SessionError::InvalidJwk("c".to_string()), "InvalidJwk"),
+            (SessionError::ParseError("d".to_string()), "ParseError"),
+            (SessionError::Crypto("e".to_string()), "Crypto"),
+        ];
+        for (error, code) in errors {
+            assert_eq!(error.code(), code);
+            assert_eq!(String::from(error.clone()), error.to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_session_error_js_shape() {
+        let mut manager = SessionManager::new().unwrap();
+        let error = manager.create_session_key(None).unwrap_err();
+        assert_eq!(
+            serde_json::to_value(error.js_error()).unwrap(),
+            serde_json::json!({
+                "code": "KeyExists",
+                "message": "key already exists: default",
+            })
+        );
     }
 
     #[tokio::test]
@@ -355,6 +1630,97 @@ pub mod test {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_set_did_method() {
+        let mut manager = SessionManager::new().unwrap();
+        let did_key = manager.get_did(None).unwrap();
+        assert!(did_key.starts_with("did:key:"));
+
+        manager.set_did_method(
+            "pkh:eip155:1:0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(
+            manager.get_did(None).unwrap(),
+            "did:pkh:eip155:1:0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+        );
+        // the SIWE uri stays the session key
+        let message = manager.build_message(test_params(), None, None).unwrap();
+        assert_eq!(message.uri.as_str(), did_key);
+
+        manager.set_did_method(DidMethodKind::Key);
+        assert_eq!(manager.get_did(None).unwrap(), did_key);
+    }
+
+    #[tokio::test]
+    async fn test_get_did_pkh_requires_key() {
+        let mut manager = SessionManager::new().unwrap();
+        manager.set_did_method(
+            "pkh:eip155:1:0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(
+            manager.get_did(Some("missing".to_string())),
+            Err("key not found: missing".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parse_did_method() {
+        assert_eq!("key".parse::<DidMethodKind>(), Ok(DidMethodKind::Key));
+        assert_eq!(
+            "pkh:eip155:137:0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".parse::<DidMethodKind>(),
+            Ok(DidMethodKind::Pkh {
+                chain_id: 137,
+                address: "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string(),
+            })
+        );
+        assert!("web".parse::<DidMethodKind>().is_err());
+        assert!("pkh:eip155:mainnet:0x5aAe"
+            .parse::<DidMethodKind>()
+            .is_err());
+        assert_eq!(
+            "pkh:eip155:1:garbage".parse::<DidMethodKind>(),
+            Err("invalid Ethereum address 'garbage': expected 20 hex-encoded bytes".to_string())
+        );
+        assert!("pkh:eip155:1:0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeA"
+            .parse::<DidMethodKind>()
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_did_vm() {
+        let manager = SessionManager::new().unwrap();
+        let vm = manager.get_did_vm(None).unwrap();
+        let (did, fragment) = vm.split_once('#').unwrap();
+        assert!(did.starts_with("did:key:z6Mk"));
+        assert_eq!(did.rsplit_once(':').unwrap().1, fragment);
+
+        let message = manager.build_message(test_params(), None, None).unwrap();
+        assert_eq!(message.uri.as_str(), vm);
+    }
+
+    #[tokio::test]
+    async fn test_get_did_pkh() {
+        let manager = SessionManager::new().unwrap();
+        let result = manager.get_did_pkh(1, "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed");
+        assert_eq!(
+            result.unwrap(),
+            "did:pkh:eip155:1:0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_did_pkh_invalid_address() {
+        let manager = SessionManager::new().unwrap();
+        assert!(manager.get_did_pkh(1, "0x1234").is_err());
+        assert!(manager
+            .get_did_pkh(1, "0xZZAeb6053F3E94C9b9A09f33669435E7Ef1BeAed")
+            .is_err());
+    }
+
     #[tokio::test]
     async fn test_get_private_key() {
         let manager = SessionManager::new().unwrap();
@@ -362,6 +1728,102 @@ pub mod test {
         assert!(result.is_ok());
     }
 
+    /// Decode the JSON header of a compact JWS.
+    fn jws_header(jws: &str) -> Value {
+        let header = jws.split('.').next().unwrap();
+        serde_json::from_slice(&URL_SAFE_NO_PAD.decode(header).unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_sign_jws() {
+        let manager = SessionManager::new().unwrap();
+        let jws = manager
+            .sign_jws("{\"aud\":\"tinycloud\"}".to_string(), None)
+            .unwrap();
+        assert_eq!(jws.split('.').count(), 3);
+        assert_eq!(jws_header(&jws)["alg"], "EdDSA");
+    }
+
+    #[tokio::test]
+    async fn test_sign_jws_secp256k1() {
+        let mut manager = SessionManager::new().unwrap();
+        manager
+            .create_session_key_with_algorithm(
+                Some("secp256k1".to_string()),
+                KeyAlgorithm::Secp256k1,
+            )
+            .unwrap();
+        let jws = manager
+            .sign_jws("payload".to_string(), Some("secp256k1".to_string()))
+            .unwrap();
+        assert_eq!(jws.split('.').count(), 3);
+        assert_eq!(jws_header(&jws)["alg"], "ES256K");
+    }
+
+    #[tokio::test]
+    async fn test_sign_jws_missing_key() {
+        let manager = SessionManager::new().unwrap();
+        assert!(manager
+            .sign_jws("payload".to_string(), Some("missing".to_string()))
+            .is_err());
+    }
+
+    fn import_test_jwk(manager: &mut SessionManager, key_id: &str, jwk: Value) {
+        let key: JWK = serde_json::from_value(jwk).unwrap();
+        manager
+            .import_session_key(key, Some(key_id.to_string()), false)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_compressed_pubkey_hex() {
+        let mut manager = SessionManager::new().unwrap();
+        import_test_jwk(
+            &mut manager,
+            "even",
+            serde_json::json!({
+                "kty": "EC",
+                "crv": "secp256k1",
+                "x": "TjuBr5wiNMrQnWec5gNe0TkjR85kzkBfXc02Iool3m4",
+                "y": "R_01xCFdHt9T5vg940RhXOcZvbD9h49u128G3Sd5Vt4",
+                "d": "TAiDppECk31iMUcbXbtiBP5RKWFwgnkq5GjQGj82Ixg",
+            }),
+        );
+        // 6 * G
+        import_test_jwk(
+            &mut manager,
+            "odd",
+            serde_json::json!({
+                "kty": "EC",
+                "crv": "secp256k1",
+                "x": "__l71XVe7qQgRToUNVI104L2Ry-FaKGLLwV6FGApdVY",
+                "y": "rhJ3eqz7tiDzvpYBf0XFYN6A8PZRj-SgPIcMNrB18pc",
+                "d": "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAY",
+            }),
+        );
+
+        assert_eq!(
+            manager.compressed_pubkey_hex(Some("even".to_string())),
+            Ok("0x024e3b81af9c2234cad09d679ce6035ed1392347ce64ce405f5dcd36228a25de6e".to_string())
+        );
+        assert_eq!(
+            manager.compressed_pubkey_hex(Some("odd".to_string())),
+            Ok("0x03fff97bd5755eeea420453a14355235d382f6472f8568a18b2f057a1460297556".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compressed_pubkey_hex_not_secp256k1() {
+        let mut manager = SessionManager::new().unwrap();
+        manager
+            .create_session_key_with_algorithm(Some("passkey".to_string()), KeyAlgorithm::P256)
+            .unwrap();
+        assert!(manager.compressed_pubkey_hex(None).is_err());
+        assert!(manager
+            .compressed_pubkey_hex(Some("passkey".to_string()))
+            .is_err());
+    }
+
     #[tokio::test]
     async fn test_jwk() {
         let manager = SessionManager::new().unwrap();
@@ -371,6 +1833,37 @@ pub mod test {
         assert!(jwk.contains("crv\":\"Ed25519\""));
     }
 
+    #[tokio::test]
+    async fn test_key_summary() {
+        let mut manager = SessionManager::new().unwrap();
+        let key_id = Some("custom_key".to_string());
+        manager.create_session_key(key_id.clone()).unwrap();
+
+        let summary = manager.summarize_key(key_id.clone()).unwrap();
+        assert_eq!(summary.key_id, "custom_key");
+        assert_eq!(summary.did, manager.get_did(key_id.clone()).unwrap());
+        assert!(!summary.has_session);
+
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["alg"], "EdDSA");
+        assert_eq!(json["hasSession"], false);
+        assert!(json["publicJwk"].get("x").is_some());
+        assert!(json["publicJwk"].get("d").is_none());
+
+        let session = test_session(&manager, key_id.clone());
+        manager.update_session(session, key_id.clone()).unwrap();
+        assert!(manager.summarize_key(key_id).unwrap().has_session);
+        assert!(manager.summarize_key(Some("missing".to_string())).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_public_jwk() {
+        let manager = SessionManager::new().unwrap();
+        let jwk: Value = serde_json::from_str(&manager.public_jwk(None).unwrap()).unwrap();
+        assert!(jwk.get("x").is_some());
+        assert!(jwk.get("d").is_none());
+    }
+
     #[tokio::test]
     async fn test_import_existing_session_key_without_override() {
         let mut manager = SessionManager::new().unwrap();
@@ -380,23 +1873,767 @@ pub mod test {
         assert!(result.is_ok());
         let result = manager.import_session_key(key, Some("imported_key".to_string()), false);
         assert!(result.is_err()); // expect error because override is false
+        assert_eq!(result.unwrap_err().code(), "KeyExists");
     }
 
-    // #[tokio::test]
-    // async fn test_update_session() {
-    //     let mut manager = SessionManager::new().unwrap();
+    #[tokio::test]
+    async fn test_export_import_state() {
+        let mut manager = SessionManager::new().unwrap();
+        manager
+            .create_session_key(Some("second".to_string()))
+            .unwrap();
+        add_test_action(&mut manager, "https://example.com/", "tinycloud.kv/get");
+
+        let state = manager.export_state().unwrap();
+        let restored = SessionManager::import_state(state).unwrap();
+
+        let keys: HashSet<_> = restored.list_session_keys().into_iter().collect();
+        assert_eq!(
+            keys,
+            HashSet::from(["default".to_string(), "second".to_string()])
+        );
+        for key_id in ["default", "second"] {
+            assert_eq!(
+                restored.get_did(Some(key_id.to_string())),
+                manager.get_did(Some(key_id.to_string()))
+            );
+        }
+        assert_eq!(
+            serde_json::to_value(&restored.capability).unwrap(),
+            serde_json::to_value(&manager.capability).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_corrupt_state() {
+        assert!(SessionManager::import_state("{not json".to_string()).is_err());
+    }
+
+    pub fn add_test_action(manager: &mut SessionManager, target: &str, action: &str) {
+        assert!(manager.add_actions(target, &[action.to_string()]));
+    }
+
+    #[tokio::test]
+    async fn test_add_actions() {
+        let mut manager = SessionManager::new().unwrap();
+        let actions = [
+            "tinycloud.kv/get".to_string(),
+            "tinycloud.kv/put".to_string(),
+        ];
+        assert!(manager.add_actions("https://example.com/", &actions));
+        assert_eq!(
+            manager.capability.abilities()["https://example.com/"].len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_add_actions_to_explicit_resource() {
+        let mut manager = SessionManager::new().unwrap();
+        let abilities = ["kv/read".to_string(), "kv/write".to_string()];
+        assert!(manager.add_actions("kv://orbit/path", &abilities));
+        assert_eq!(
+            manager.list_abilities()["kv://orbit/path"],
+            vec!["kv/read", "kv/write"]
+        );
+
+        assert!(!manager.add_actions("kv://orbit/path", &["kv read".to_string()]));
+        assert!(!manager.add_actions("kv://orbit /path", &abilities[..1]));
+        assert_eq!(manager.list_abilities().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_add_actions_malformed_target() {
+        let mut manager = SessionManager::new().unwrap();
+        let actions = ["tinycloud.kv/get".to_string()];
+        assert!(!manager.add_actions("not a valid uri", &actions));
+        assert!(manager.capability.abilities().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_add_actions_malformed_action() {
+        let mut manager = SessionManager::new().unwrap();
+        let actions = ["tinycloud.kv/get".to_string(), "not an action".to_string()];
+        assert!(!manager.add_actions("https://example.com/", &actions));
+        assert!(manager.capability.abilities().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_add_actions_with_fields() {
+        let mut manager = SessionManager::new().unwrap();
+        let fields: BTreeMap<String, Value> = serde_json::from_value(serde_json::json!({
+            "maxSize": 1048576,
+            "pathPrefix": "photos/",
+        }))
+        .unwrap();
+        assert!(manager.add_actions_with_fields(
+            "https://example.com/",
+            &["tinycloud.kv/put".to_string()],
+            fields,
+        ));
+        add_test_action(&mut manager, "https://example.com/", "tinycloud.kv/get");
+
+        let message = manager.build_message(test_params(), None, None).unwrap();
+        let recap = message_recap_json(&message).unwrap();
+        let abilities = &recap["att"]["https://example.com/"];
+        assert_eq!(
+            abilities["tinycloud.kv/put"],
+            serde_json::json!([{ "maxSize": 1048576, "pathPrefix": "photos/" }])
+        );
+        assert!(abilities.get("tinycloud.kv/get").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_max_abilities() {
+        let mut manager = SessionManager::new().unwrap();
+        manager.set_max_abilities(3);
+        let actions = [
+            "tinycloud.kv/get".to_string(),
+            "tinycloud.kv/put".to_string(),
+        ];
+        assert!(manager.add_actions("https://example.com/", &actions));
+        // re-adding existing actions does not count towards the limit
+        assert!(manager.add_actions("https://example.com/", &actions));
+        assert!(!manager.add_actions("https://other.example/", &actions));
+        assert_eq!(manager.ability_count(), 2);
+
+        assert!(manager.add_actions("https://other.example/", &actions[..1]));
+        assert_eq!(manager.ability_count(), 3);
+        assert!(!manager.add_actions("https://example.com/", &["tinycloud.kv/del".to_string()]));
+        assert_eq!(manager.ability_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_default_max_abilities() {
+        let mut manager = SessionManager::new().unwrap();
+        let actions: Vec<String> = (0..DEFAULT_MAX_ABILITIES)
+            .map(|i| format!("tinycloud.kv/action{}", i))
+            .collect();
+        assert!(manager.add_actions("https://example.com/", &actions));
+        assert!(!manager.add_actions("https://other.example/", &actions[..1]));
+    }
+
+    #[tokio::test]
+    async fn test_build_recap_too_large() {
+        let mut manager = SessionManager::new().unwrap();
+        let target = format!("https://example.com/{}", "a".repeat(MAX_RECAP_BYTES));
+        add_test_action(&mut manager, &target, "tinycloud.kv/get");
+        let error = manager
+            .build_message(test_params(), None, None)
+            .unwrap_err();
+        assert!(error.contains("exceeds the limit"));
+    }
+
+    #[tokio::test]
+    async fn test_add_targeted_action_with_expiry() {
+        let mut manager = SessionManager::new().unwrap();
+        let target = "https://example.com/".to_string();
+        assert!(manager.add_targeted_action_with_expiry(
+            "tinycloud.kv".to_string(),
+            target.clone(),
+            "get".to_string(),
+            Some(1704067200),
+            Some(1706745600),
+        ));
+        assert!(manager.add_targeted_action_with_expiry(
+            "tinycloud.kv".to_string(),
+            target.clone(),
+            "put".to_string(),
+            None,
+            Some(1704070800),
+        ));
+
+        let message = manager.build_message(test_params(), None, None).unwrap();
+        let recap = message_recap_json(&message).unwrap();
+        let abilities = &recap["att"][target.as_str()];
+        assert_eq!(
+            abilities["tinycloud.kv/get"],
+            serde_json::json!([{ "nbf": 1704067200, "exp": 1706745600 }])
+        );
+        assert_eq!(
+            abilities["tinycloud.kv/put"],
+            serde_json::json!([{ "exp": 1704070800 }])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_build_merge_recap_resources() {
+        let previous_recap = test_recap(&[
+            ("https://example.com/", "tinycloud.kv/list"),
+            ("https://previous.example/", "tinycloud.kv/get"),
+        ]);
+
+        let mut manager = SessionManager::new().unwrap();
+        add_test_action(&mut manager, "https://example.com/", "tinycloud.kv/put");
+        let mut params = test_params();
+        params.resources.push(previous_recap);
+        params.merge_resources = true;
+
+        let message = manager.build_message(params, None, None).unwrap();
+        assert_eq!(message.resources.len(), 2);
+        assert_eq!(
+            message.resources[0].as_str(),
+            "https://example.com/resource"
+        );
+        let recap = message_recap_json(&message).unwrap();
+        let att = recap["att"].as_object().unwrap();
+        assert!(att["https://example.com/"]
+            .get("tinycloud.kv/put")
+            .is_some());
+        assert!(att["https://example.com/"]
+            .get("tinycloud.kv/list")
+            .is_some());
+        assert!(att["https://previous.example/"]
+            .get("tinycloud.kv/get")
+            .is_some());
+        // the manager's own capability is left as it was
+        assert!(!manager
+            .list_abilities()
+            .contains_key("https://previous.example/"));
+    }
+
+    #[tokio::test]
+    async fn test_build_generated_statement_with_merged_resources() {
+        let previous_recap = test_recap(&[("https://previous.example/", "tinycloud.kv/get")]);
+
+        let mut manager = SessionManager::new().unwrap();
+        add_test_action(&mut manager, "https://example.com/", "tinycloud.kv/put");
+        let mut params = test_params();
+        params.resources.push(previous_recap);
+        params.merge_resources = true;
+        params.generate_statement = true;
+
+        let message = manager.build_message(params, None, None).unwrap();
+        // the recap appends its own description of the grants
+        assert!(message.statement.unwrap().starts_with(
+            "This request will allow the application to perform: \
+             tinycloud.kv/put on https://example.com/; \
+             tinycloud.kv/get on https://previous.example/."
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_build_without_merge_keeps_recap_resource() {
+        let previous_recap = test_recap(&[("https://previous.example/", "tinycloud.kv/get")]);
+
+        let mut manager = SessionManager::new().unwrap();
+        add_test_action(&mut manager, "https://example.com/", "tinycloud.kv/put");
+        let mut params = test_params();
+        params.resources.push(previous_recap.clone());
+
+        let message = manager.build_message(params, None, None).unwrap();
+        assert!(message
+            .resources
+            .iter()
+            .any(|resource| resource.as_str() == previous_recap));
+    }
+
+    #[tokio::test]
+    async fn test_build_details_matches_build() {
+        let mut manager = SessionManager::new().unwrap();
+        add_test_action(&mut manager, "https://example.com/", "tinycloud.kv/get");
+
+        let details = manager.build_details(test_params(), None, None).unwrap();
+        let message = manager
+            .build_message(test_params(), None, None)
+            .unwrap()
+            .to_string();
+        assert_eq!(details.message, message);
+        assert_eq!(details.uri, manager.get_did(None).unwrap());
+        assert!(details.decoded_recap["att"]["https://example.com/"]
+            .get("tinycloud.kv/get")
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_build_rejects_zero_chain_id() {
+        let manager = SessionManager::new().unwrap();
+        let mut params = test_params();
+        params.chain_id = 0;
+        let result = manager.build_message(params, None, None);
+        assert!(result.unwrap_err().contains("chain id"));
+    }
+
+    #[tokio::test]
+    async fn test_supported_chain_name() {
+        assert_eq!(supported_chain_name(1), Some("Ethereum".to_string()));
+        assert_eq!(supported_chain_name(8453), Some("Base".to_string()));
+        assert_eq!(
+            supported_chain_name(42161),
+            Some("Arbitrum One".to_string())
+        );
+        assert_eq!(supported_chain_name(987654321), None);
+    }
+
+    #[tokio::test]
+    async fn test_build_default_version() {
+        let manager = SessionManager::new().unwrap();
+        let message = manager.build_message(test_params(), None, None).unwrap();
+        assert_eq!(message.version, SiweVersion::V1);
+    }
+
+    #[tokio::test]
+    async fn test_build_explicit_version() {
+        let manager = SessionManager::new().unwrap();
+        let mut params = test_params();
+        params.version = Some("1".to_string());
+        let message = manager.build_message(params, None, None).unwrap();
+        assert_eq!(message.version, SiweVersion::V1);
+    }
+
+    #[tokio::test]
+    async fn test_build_invalid_version() {
+        let manager = SessionManager::new().unwrap();
+        let mut params = test_params();
+        params.version = Some("2".to_string());
+        let result = manager.build_message(params, None, None);
+        assert!(result.unwrap_err().contains("unsupported siwe version"));
+    }
+
+    #[tokio::test]
+    async fn test_export_import_capability() {
+        let mut source = SessionManager::new().unwrap();
+        add_test_action(&mut source, "https://example.com/", "tinycloud.kv/get");
+        add_test_action(&mut source, "https://example.com/", "tinycloud.kv/put");
+        add_test_action(&mut source, "https://other.example/", "tinycloud.kv/list");
+
+        let mut target = SessionManager::new().unwrap();
+        add_test_action(&mut target, "https://stale.example/", "tinycloud.kv/del");
+        target
+            .import_capability(source.export_capability().unwrap())
+            .unwrap();
+
+        assert_eq!(target.list_abilities(), source.list_abilities());
+        assert!(!target
+            .list_abilities()
+            .contains_key("https://stale.example/"));
+        assert_eq!(
+            target.list_abilities()["https://example.com/"],
+            vec!["tinycloud.kv/get", "tinycloud.kv/put"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_invalid_capability() {
+        let mut manager = SessionManager::new().unwrap();
+        add_test_action(&mut manager, "https://example.com/", "tinycloud.kv/get");
+        assert!(manager.import_capability("not json".to_string()).is_err());
+        assert!(manager.has_capabilities());
+    }
+
+    #[tokio::test]
+    async fn test_build_checksummed_address() {
+        let manager = SessionManager::new().unwrap();
+        let message = manager.build_message(test_params(), None, None).unwrap();
+        assert_eq!(
+            eip55(&message.address),
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_build_single_case_address() {
+        let manager = SessionManager::new().unwrap();
+        for address in [
+            "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed",
+            "0x5AAEB6053F3E94C9B9A09F33669435E7EF1BEAED",
+            "5aaeb6053f3e94c9b9a09f33669435e7ef1beaed",
+        ] {
+            let mut params = test_params();
+            params.address = address.to_string();
+            let message = manager.build_message(params, None, None).unwrap();
+            assert_eq!(
+                eip55(&message.address),
+                "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_build_bad_checksum_address() {
+        let manager = SessionManager::new().unwrap();
+        let mut params = test_params();
+        params.address = "0x5AAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string();
+        let result = manager.build_message(params, None, None);
+        assert_eq!(
+            result.unwrap_err(),
+            "address failed EIP-55 checksum".to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_build_short_address() {
+        let manager = SessionManager::new().unwrap();
+        let mut params = test_params();
+        params.address = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeA".to_string();
+        assert!(manager.build_message(params, None, None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_build_generated_statement() {
+        let mut manager = SessionManager::new().unwrap();
+        add_test_action(&mut manager, "https://example.com/", "tinycloud.kv/get");
+        add_test_action(&mut manager, "https://example.com/", "tinycloud.kv/put");
+        add_test_action(&mut manager, "https://other.example/", "tinycloud.kv/list");
+        let mut params = test_params();
+        params.generate_statement = true;
+
+        let details = manager.build_details(params, None, None).unwrap();
+        let statement = details.statement.unwrap();
+        assert!(statement.starts_with(
+            "This request will allow the application to perform: \
+             tinycloud.kv/get, tinycloud.kv/put on https://example.com/; \
+             tinycloud.kv/list on https://other.example/."
+        ));
+        assert!(!statement.contains('\n'));
+    }
+
+    #[tokio::test]
+    async fn test_build_generated_statement_with_address_label() {
+        let mut manager = SessionManager::new().unwrap();
+        add_test_action(&mut manager, "https://example.com/", "tinycloud.kv/get");
+        let mut params = test_params();
+        params.generate_statement = true;
+        params.address_label = Some("vitalik.eth\n".to_string());
+
+        let message = manager.build_message(params, None, None).unwrap();
+        let statement = message.statement.clone().unwrap();
+        assert!(statement.starts_with(
+            "Signing in as vitalik.eth. This request will allow the application to perform: \
+             tinycloud.kv/get on https://example.com/."
+        ));
+        assert!(!statement.contains('\n'));
+        assert_eq!(
+            eip55(&message.address),
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+        );
+        assert!(message
+            .to_string()
+            .contains("\n0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed\n"));
+    }
+
+    #[tokio::test]
+    async fn test_build_explicit_statement_not_generated() {
+        let mut manager = SessionManager::new().unwrap();
+        add_test_action(&mut manager, "https://example.com/", "tinycloud.kv/get");
+        let mut params = test_params();
+        params.statement = Some("Sign in to example.com.".to_string());
+        params.generate_statement = true;
+
+        let details = manager.build_details(params, None, None).unwrap();
+        let statement = details.statement.unwrap();
+        assert!(statement.starts_with("Sign in to example.com."));
+        assert!(!statement.contains("This request will allow"));
+    }
+
+    #[tokio::test]
+    async fn test_build_https_custom_uri() {
+        let manager = SessionManager::new().unwrap();
+        let message = manager
+            .build_message(
+                test_params(),
+                None,
+                Some("https://example.com/callback".to_string()),
+            )
+            .unwrap();
+        assert_eq!(message.uri.as_str(), "https://example.com/callback");
+    }
+
+    #[tokio::test]
+    async fn test_build_invalid_custom_uri() {
+        let manager = SessionManager::new().unwrap();
+        let error = manager
+            .build_message(
+                test_params(),
+                None,
+                Some("https://exa mple.com".to_string()),
+            )
+            .unwrap_err();
+        assert!(error.starts_with("invalid custom URI 'https://exa mple.com'"));
+
+        let error = manager
+            .build_message(test_params(), None, Some("ftp://example.com/".to_string()))
+            .unwrap_err();
+        assert_eq!(
+            error,
+            "invalid custom URI 'ftp://example.com/': scheme must be one of https, http, did"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validate_params() {
+        assert_eq!(validate_params(&test_params()), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_validate_params_reports_all_errors() {
+        let mut params = test_params();
+        params.address = "0x5AAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string();
+        params.chain_id = 0;
+        params.nonce = Some("short".to_string());
+        params.issued_at = "yesterday".to_string();
+        params.expiration_time = Some("2024-13-01T00:00:00Z".to_string());
+        params.not_before = Some("2024-01-01T00:00:00.000Z".to_string());
+
+        let errors = validate_params(&params).unwrap_err();
+        assert_eq!(errors.len(), 5);
+        assert_eq!(errors[0], "address failed EIP-55 checksum");
+        assert_eq!(errors[1], "invalid chain id: chain id must be non-zero");
+        assert_eq!(
+            errors[2],
+            "nonce must be at least 8 alphanumeric characters"
+        );
+        assert!(errors[3].starts_with("unable to parse issuedAt as a timestamp"));
+        assert!(errors[4].starts_with("unable to parse expirationTime as a timestamp"));
+    }
+
+    #[tokio::test]
+    async fn test_reset_all() {
+        let mut manager = SessionManager::new().unwrap();
+        let did = manager.get_did(None).unwrap();
+        manager
+            .create_session_key(Some("custom_key".to_string()))
+            .unwrap();
+        add_test_action(&mut manager, "https://example.com/", "tinycloud.kv/get");
+
+        manager.reset_all().unwrap();
+
+        assert_eq!(manager.list_session_keys(), vec!["default".to_string()]);
+        assert!(!manager.has_capabilities());
+        assert_ne!(manager.get_did(None).unwrap(), did);
+    }
+
+    #[tokio::test]
+    async fn test_remove_namespace() {
+        let mut manager = SessionManager::new().unwrap();
+        add_test_action(&mut manager, "kv://example/photos", "tinycloud.kv/get");
+        add_test_action(&mut manager, "kv://example/docs", "tinycloud.kv/put");
+        add_test_action(&mut manager, "https://example.com/", "tinycloud.kv/get");
 
-    //     let session = Default::default();
-    //     let result = manager.update_session(session, Some("default".to_string()));
-    //     assert!(result.is_ok());
+        assert!(manager.remove_namespace("kv"));
+        let abilities = manager.list_abilities();
+        assert_eq!(
+            abilities.keys().collect::<Vec<_>>(),
+            vec!["https://example.com/"]
+        );
+
+        assert!(!manager.remove_namespace("kv"));
+        assert!(!manager.remove_namespace("http"));
+        assert_eq!(manager.list_abilities(), abilities);
+    }
+
+    #[tokio::test]
+    async fn test_clone_is_independent() {
+        let mut manager = SessionManager::new().unwrap();
+        manager
+            .create_session_key(Some("custom_key".to_string()))
+            .unwrap();
+        add_test_action(&mut manager, "https://example.com/", "tinycloud.kv/get");
+        let snapshot = manager.clone();
+        let did = manager.get_did(None).unwrap();
+        let abilities = manager.list_abilities();
+
+        manager.reset_all().unwrap();
+        add_test_action(&mut manager, "https://other.example/", "tinycloud.kv/put");
+
+        assert_eq!(
+            snapshot.list_session_keys_sorted(),
+            vec!["default", "custom_key"]
+        );
+        assert_eq!(snapshot.get_did(None).unwrap(), did);
+        assert_eq!(snapshot.list_abilities(), abilities);
+        assert_ne!(manager.get_did(None).unwrap(), did);
+    }
+
+    #[tokio::test]
+    async fn test_has_capabilities() {
+        let mut manager = SessionManager::new().unwrap();
+        assert!(!manager.has_capabilities());
+
+        add_test_action(&mut manager, "https://example.com/", "tinycloud.kv/get");
+        assert!(manager.has_capabilities());
+
+        manager.reset_capability();
+        assert!(!manager.has_capabilities());
+    }
 
-    //     // Check if session info is stored properly
-    //     if let Some(session_info) = manager.sessions.get("default") {
-    //         assert!(session_info.session.is_some());
-    //         assert_eq!(session_info.session.as_ref().unwrap().id, "session1");
-    //         assert_eq!(session_info.session.as_ref().unwrap().timestamp, 123456);
-    //     } else {
-    //         panic!("Session not found");
-    //     }
-    // }
+    pub fn test_params() -> SiweParams {
+        SiweParams {
+            address: "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string(),
+            chain_id: 1,
+            domain: "example.com".to_string(),
+            nonce: Some("abcdef1234567890".to_string()),
+            issued_at: "2024-01-01T00:00:00.000Z".to_string(),
+            resources: vec!["https://example.com/resource".to_string()],
+            ..Default::default()
+        }
+    }
+
+    /// The recap resource of a message granting `actions`, given as (target, action) pairs.
+    pub fn test_recap(actions: &[(&str, &str)]) -> String {
+        let mut manager = SessionManager::new().unwrap();
+        for (target, action) in actions {
+            add_test_action(&mut manager, target, action);
+        }
+        manager
+            .build_message(test_params(), None, None)
+            .unwrap()
+            .resources
+            .last()
+            .unwrap()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_build_with_duration() {
+        let manager = SessionManager::new().unwrap();
+        let mut params = test_params();
+        params.valid_for_secs = Some(3600);
+        let message = manager.build_message(params, None, None).unwrap();
+        assert_eq!(
+            message.expiration_time.unwrap().to_string(),
+            "2024-01-01T01:00:00Z"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_build_explicit_expiration_takes_precedence() {
+        let manager = SessionManager::new().unwrap();
+        let mut params = test_params();
+        params.expiration_time = Some("2024-06-01T00:00:00.000Z".to_string());
+        params.valid_for_secs = Some(3600);
+        let message = manager.build_message(params, None, None).unwrap();
+        assert_eq!(
+            message.expiration_time.unwrap().to_string(),
+            "2024-06-01T00:00:00.000Z"
+        );
+    }
+
+    pub fn test_session(manager: &SessionManager, key_id: Option<String>) -> Session {
+        let jwk = manager.get_private_key(key_id.clone()).unwrap();
+        serde_json::from_value(serde_json::json!({
+            "delegationHeader": { "Authorization": "Bearer test" },
+            "delegationCid": "bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi",
+            "jwk": jwk,
+            "spaceId": "tinycloud:pkh:eip155:1:0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed:default",
+            "verificationMethod": manager.get_did(key_id).unwrap(),
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_update_session() {
+        let mut manager = SessionManager::new().unwrap();
+
+        let session = test_session(&manager, None);
+        let result = manager.update_session(session, Some("default".to_string()));
+        assert!(result.is_ok());
+
+        // Check if session info is stored properly
+        if let Some(session_info) = manager.sessions.get("default") {
+            let session = session_info.session.as_ref().unwrap();
+            assert_eq!(session.verification_method, manager.get_did(None).unwrap());
+        } else {
+            panic!("Session not found");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_session_key_id_from_jwk() {
+        let mut manager = SessionManager::new().unwrap();
+        manager
+            .create_session_key(Some("custom_key".to_string()))
+            .unwrap();
+
+        let session = test_session(&manager, Some("custom_key".to_string()));
+        assert!(manager.update_session(session, None).is_ok());
+        assert!(manager.sessions["custom_key"].session.is_some());
+        assert!(manager.sessions["default"].session.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_update_session_without_key_id() {
+        let mut manager = SessionManager::new().unwrap();
+
+        let mut session = test_session(&manager, None);
+        session.jwk.key_id = None;
+        let result = manager.update_session(session, None);
+        assert_eq!(result, Err("No key_id provided".to_string()));
+    }
+
+    /// Store a test session signed over a SIWE message expiring at 2024-01-02T00:00:00Z.
+    fn store_expiring_session(manager: &mut SessionManager) {
+        let mut params = test_params();
+        params.expiration_time = Some("2024-01-02T00:00:00.000Z".to_string());
+        let siwe = manager
+            .build_message(params, None, None)
+            .unwrap()
+            .to_string();
+        let session = test_session(manager, None);
+        manager
+            .update_session_with_siwe(session, Some(siwe), None)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_session_not_expired() {
+        let mut manager = SessionManager::new().unwrap();
+        store_expiring_session(&mut manager);
+        // 2024-01-01T12:00:00Z
+        assert_eq!(manager.is_session_expired(None, 1704110400), Ok(false));
+    }
+
+    #[tokio::test]
+    async fn test_session_expired() {
+        let mut manager = SessionManager::new().unwrap();
+        store_expiring_session(&mut manager);
+        // 2024-01-02T00:00:00Z
+        assert_eq!(manager.is_session_expired(None, 1704153600), Ok(true));
+        // 2024-01-03T00:00:00Z
+        assert_eq!(manager.is_session_expired(None, 1704240000), Ok(true));
+    }
+
+    #[tokio::test]
+    async fn test_missing_session_expired() {
+        let mut manager = SessionManager::new().unwrap();
+        assert!(manager.is_session_expired(None, 1704110400).is_err());
+        assert!(manager
+            .is_session_expired(Some("nonexistent_key".to_string()), 1704110400)
+            .is_err());
+
+        store_expiring_session(&mut manager);
+        manager.clear_sessions();
+        assert!(manager.is_session_expired(None, 1704110400).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_session_without_siwe_expired() {
+        let mut manager = SessionManager::new().unwrap();
+        store_expiring_session(&mut manager);
+        let session = test_session(&manager, None);
+        manager.update_session(session, None).unwrap();
+        assert_eq!(
+            manager.is_session_expired(None, 1704110400),
+            Err("no SIWE message stored for the session of key: default".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_clear_sessions() {
+        let mut manager = SessionManager::new().unwrap();
+        manager
+            .create_session_key(Some("custom_key".to_string()))
+            .unwrap();
+        let key_id = Some("custom_key".to_string());
+        let did = manager.get_did(key_id.clone()).unwrap();
+        let session = test_session(&manager, key_id.clone());
+        manager.update_session(session, key_id.clone()).unwrap();
+
+        manager.clear_sessions();
+
+        let session_info = &manager.sessions["custom_key"];
+        assert!(session_info.session.is_none());
+        assert!(session_info.key.is_some());
+        assert_eq!(manager.get_did(key_id).unwrap(), did);
+    }
 }
@@ -1,6 +1,10 @@
 mod manager;
+mod recap;
+mod verify;
 mod wasm;
 
 pub mod types;
 pub use manager::*;
+pub use recap::*;
+pub use verify::*;
 pub use wasm::*;
@@ -0,0 +1,163 @@
+use std::str::FromStr;
+
+use base64::{
+    alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+    Engine as _,
+};
+use serde::Serialize;
+use serde_json::Value;
+use tinycloud_sdk_rs::tinycloud_auth::{cacaos::siwe::Message, siwe_recap::Capability};
+use wasm_bindgen::prelude::*;
+
+const RECAP_PREFIX: &str = "urn:recap:";
+
+/// Recap payloads are unpadded base64url, but tolerate padding from other encoders.
+const RECAP_ENGINE: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::URL_SAFE,
+    GeneralPurposeConfig::new()
+        .with_encode_padding(false)
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Whether a resource URI is a ReCap capability.
+pub fn is_recap_uri(uri: &str) -> bool {
+    uri.starts_with(RECAP_PREFIX)
+}
+
+/// Decode a `urn:recap:` resource URI into its capability.
+pub fn decode_recap_uri(uri: &str) -> Result<Capability<Value>, String> {
+    let encoded = uri
+        .strip_prefix(RECAP_PREFIX)
+        .ok_or_else(|| format!("resource is not a recap URI: {}", uri))?;
+    let json = RECAP_ENGINE
+        .decode(encoded)
+        .map_err(|e| format!("invalid base64 encoding for recap: {}", e))?;
+    serde_json::from_slice(&json).map_err(|e| format!("invalid recap payload: {}", e))
+}
+
+/// Combine the targets, actions and proofs of two capabilities.
+///
+/// Actions present in both keep the constraints from `capability`.
+pub fn merge_capabilities(
+    capability: &Capability<Value>,
+    other: &Capability<Value>,
+) -> Result<Capability<Value>, String> {
+    let to_json = |capability| {
+        serde_json::to_value(capability).map_err(|e| format!("failed to serialize recap: {}", e))
+    };
+    let mut merged = to_json(capability)?;
+    let other = to_json(other)?;
+
+    if let (Some(att), Some(other_att)) = (
+        merged.get_mut("att").and_then(Value::as_object_mut),
+        other.get("att").and_then(Value::as_object),
+    ) {
+        for (target, other_abilities) in other_att {
+            let abilities = att
+                .entry(target.clone())
+                .or_insert_with(|| Value::Object(Default::default()));
+            if let (Some(abilities), Some(other_abilities)) =
+                (abilities.as_object_mut(), other_abilities.as_object())
+            {
+                for (ability, nota_bene) in other_abilities {
+                    abilities
+                        .entry(ability.clone())
+                        .or_insert_with(|| nota_bene.clone());
+                }
+            }
+        }
+    }
+    if let (Some(prf), Some(other_prf)) = (
+        merged.get_mut("prf").and_then(Value::as_array_mut),
+        other.get("prf").and_then(Value::as_array),
+    ) {
+        for proof in other_prf {
+            if !prf.contains(proof) {
+                prf.push(proof.clone());
+            }
+        }
+    }
+
+    serde_json::from_value(merged).map_err(|e| format!("invalid recap payload: {}", e))
+}
+
+/// Decode the recap resource of a SIWE message, if it has one.
+///
+/// Per EIP-5573 the recap is the last resource of the message.
+pub fn extract_recap(message: &Message) -> Result<Option<Capability<Value>>, String> {
+    match message.resources.last() {
+        Some(resource) if is_recap_uri(resource.as_str()) => {
+            decode_recap_uri(resource.as_str()).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Decode the recap of a SIWE message to JSON, or an empty object if it has none.
+pub fn message_recap_json(message: &Message) -> Result<Value, String> {
+    match extract_recap(message)? {
+        Some(capability) => serde_json::to_value(capability)
+            .map_err(|e| format!("failed to serialize recap: {}", e)),
+        None => Ok(Value::Object(Default::default())),
+    }
+}
+
+/// Decode the recap of a SIWE message string to JSON, or an empty object if it has none.
+pub fn recap_json(message: &str) -> Result<Value, String> {
+    let message =
+        Message::from_str(message).map_err(|e| format!("unable to parse siwe message: {}", e))?;
+    message_recap_json(&message)
+}
+
+/// Decode the recap of a SIWE message into a JS object of the granted abilities.
+#[wasm_bindgen(js_name = decodeRecap)]
+pub fn decode_recap(message: String) -> Result<JsValue, JsValue> {
+    let recap = recap_json(&message)?;
+    recap
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(JsValue::from)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::session::manager::test::{add_test_action, test_params};
+    use crate::session::SessionManager;
+
+    #[tokio::test]
+    async fn test_decode_recap_of_built_message() {
+        let mut manager = SessionManager::new().unwrap();
+        add_test_action(&mut manager, "https://example.com/", "tinycloud.kv/get");
+        add_test_action(&mut manager, "https://example.com/", "tinycloud.kv/put");
+        let siwe = manager
+            .build_message(test_params(), None, None)
+            .unwrap()
+            .to_string();
+
+        let recap = recap_json(&siwe).unwrap();
+        let abilities = recap["att"]["https://example.com/"].as_object().unwrap();
+        assert!(abilities.contains_key("tinycloud.kv/get"));
+        assert!(abilities.contains_key("tinycloud.kv/put"));
+    }
+
+    #[tokio::test]
+    async fn test_decode_recap_without_recap() {
+        let manager = SessionManager::new().unwrap();
+        let mut message = manager.build_message(test_params(), None, None).unwrap();
+        message.statement = None;
+        message.resources.clear();
+
+        let recap = recap_json(&message.to_string()).unwrap();
+        assert_eq!(recap, Value::Object(Default::default()));
+    }
+
+    #[tokio::test]
+    async fn test_decode_malformed_recap() {
+        let manager = SessionManager::new().unwrap();
+        let mut message = manager.build_message(test_params(), None, None).unwrap();
+        message.resources = vec!["urn:recap:not-a-recap!".parse().unwrap()];
+
+        assert!(recap_json(&message.to_string()).is_err());
+    }
+}
@@ -49,6 +49,18 @@ export type SiweConfig = {
     /**Human-readable ASCII assertion that the user will sign, and it must not
      * contain `\n`. */
     statement?: string;
+    /**SIWE message version, defaults to "1" (the only version defined by
+     * EIP-4361). */
+    version?: string;
+    /**When true and no statement is given, generate a statement summarizing the
+     * actions of the capability. */
+    generateStatement?: boolean;
+    /**Human-readable name of the address, such as an ENS name, included in the
+     * generated statement. The message is still signed for `address`. */
+    addressLabel?: string;
+    /**When true, `urn:recap:` resources are merged into the capability of the
+     * message instead of being added as separate resources. */
+    mergeResources?: boolean;
 }
 "#;
 
@@ -86,4 +98,74 @@ extern "C" {
 
     #[wasm_bindgen(structural, method, getter)]
     pub fn statement(this: &SiweConfig) -> Option<String>;
+
+    #[wasm_bindgen(structural, method, getter)]
+    pub fn version(this: &SiweConfig) -> Option<String>;
+
+    #[wasm_bindgen(structural, method, getter)]
+    pub fn generateStatement(this: &SiweConfig) -> Option<bool>;
+
+    #[wasm_bindgen(structural, method, getter)]
+    pub fn addressLabel(this: &SiweConfig) -> Option<String>;
+
+    #[wasm_bindgen(structural, method, getter)]
+    pub fn mergeResources(this: &SiweConfig) -> Option<bool>;
+}
+
+/// Owned copy of the fields read from a [`SiweConfig`].
+///
+/// Reading the config up front keeps the JS getters out of the message building
+/// path, so it can run without a JS host.
+#[derive(Debug, Clone, Default)]
+pub struct SiweParams {
+    pub address: String,
+    pub chain_id: u32,
+    pub domain: String,
+    pub nonce: Option<String>,
+    pub issued_at: String,
+    pub expiration_time: Option<String>,
+    pub not_before: Option<String>,
+    pub request_id: Option<String>,
+    pub resources: Vec<String>,
+    pub statement: Option<String>,
+    pub version: Option<String>,
+    /// Summarize the capability as the statement when no statement is given.
+    pub generate_statement: bool,
+    /// Display name of `address` for the generated statement.
+    pub address_label: Option<String>,
+    /// Merge recap resources into the capability rather than listing them separately.
+    pub merge_resources: bool,
+    /// Derive `expiration_time` from `issued_at` when no explicit expiration is given.
+    pub valid_for_secs: Option<u64>,
+}
+
+impl TryFrom<&SiweConfig> for SiweParams {
+    type Error = String;
+
+    fn try_from(config: &SiweConfig) -> Result<Self, Self::Error> {
+        let resources = config
+            .resources()
+            .unwrap_or_default()
+            .iter()
+            .map(|js_string| js_string.as_string())
+            .collect::<Option<Vec<String>>>()
+            .ok_or("error converting UTF-16 to UTF-8")?;
+        Ok(Self {
+            address: config.address(),
+            chain_id: config.chainId(),
+            domain: config.domain(),
+            nonce: config.nonce(),
+            issued_at: config.issuedAt(),
+            expiration_time: config.expirationTime(),
+            not_before: config.notBefore(),
+            request_id: config.requestId(),
+            resources,
+            statement: config.statement(),
+            version: config.version(),
+            generate_statement: config.generateStatement().unwrap_or(false),
+            address_label: config.addressLabel(),
+            merge_resources: config.mergeResources().unwrap_or(false),
+            valid_for_secs: None,
+        })
+    }
 }
@@ -0,0 +1,350 @@
+use std::str::FromStr;
+
+use serde::Serialize;
+use serde_wasm_bindgen::to_value;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use tinycloud_sdk_rs::tinycloud_auth::{
+    cacaos::siwe::{eip55, Message},
+    ssi::{jwk::JWK, jws},
+};
+use wasm_bindgen::prelude::*;
+
+/// The fields of a SIWE message, in a form that can be handed back to JS.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SiweFields {
+    pub domain: String,
+    pub address: String,
+    pub statement: Option<String>,
+    pub uri: String,
+    pub version: String,
+    pub chain_id: u64,
+    pub nonce: String,
+    pub issued_at: String,
+    pub expiration_time: Option<String>,
+    pub not_before: Option<String>,
+    pub request_id: Option<String>,
+    pub resources: Vec<String>,
+}
+
+impl From<&Message> for SiweFields {
+    fn from(message: &Message) -> Self {
+        Self {
+            domain: message.domain.to_string(),
+            address: eip55(&message.address),
+            statement: message.statement.clone(),
+            uri: message.uri.to_string(),
+            version: (message.version as u64).to_string(),
+            chain_id: message.chain_id,
+            nonce: message.nonce.clone(),
+            issued_at: message.issued_at.to_string(),
+            expiration_time: message.expiration_time.as_ref().map(|t| t.to_string()),
+            not_before: message.not_before.as_ref().map(|t| t.to_string()),
+            request_id: message.request_id.clone(),
+            resources: message.resources.iter().map(|r| r.to_string()).collect(),
+        }
+    }
+}
+
+/// Parse a SIWE message string into its fields.
+pub fn siwe_fields(message: &str) -> Result<SiweFields, String> {
+    let message =
+        Message::from_str(message).map_err(|e| format!("unable to parse siwe message: {}", e))?;
+    Ok(SiweFields::from(&message))
+}
+
+/// Parse a SIWE message string and return its fields as a JS object.
+#[wasm_bindgen(js_name = parseSiweMessage)]
+pub fn parse_siwe_message(message: String) -> Result<JsValue, JsValue> {
+    let fields = siwe_fields(&message)?;
+    to_value(&fields).map_err(JsValue::from)
+}
+
+/// Verify that `signature_hex` is a valid EIP-191 signature of the SIWE message by its `address`.
+///
+/// Returns `false` when the signature does not match the message signer, and an
+/// error when the message or signature cannot be decoded.
+pub fn verify_siwe_signature(message: &str, signature_hex: &str) -> Result<bool, String> {
+    let message =
+        Message::from_str(message).map_err(|e| format!("unable to parse siwe message: {}", e))?;
+    let sig_bytes = hex::decode(signature_hex.strip_prefix("0x").unwrap_or(signature_hex))
+        .map_err(|e| format!("Invalid hex encoding for signature: {}", e))?;
+    let signature: [u8; 65] = sig_bytes
+        .try_into()
+        .map_err(|_| "signature must be 65 bytes (r || s || v)".to_string())?;
+    Ok(message.verify_eip191(&signature).is_ok())
+}
+
+/// Verify a signed SIWE message.
+#[wasm_bindgen(js_name = verifySiwe)]
+pub fn verify_siwe(message: String, signature_hex: String) -> Result<bool, JsValue> {
+    Ok(verify_siwe_signature(&message, &signature_hex)?)
+}
+
+/// Clock skew tolerated between the signer and the verifier by default, in seconds.
+pub const DEFAULT_SKEW_SECS: u64 = 60;
+
+/// Whether `now_unix_secs` falls within the validity window of a SIWE message, widened by
+/// `skew_secs` on both ends: `not_before <= now + skew` and `now <= expiration + skew`.
+pub fn siwe_time_valid(
+    message: &Message,
+    now_unix_secs: u64,
+    skew_secs: u64,
+) -> Result<bool, String> {
+    let now = i128::from(now_unix_secs);
+    let skew = i128::from(skew_secs);
+    if let Some(not_before) = &message.not_before {
+        if unix_timestamp(&not_before.to_string())? > now + skew {
+            return Ok(false);
+        }
+    }
+    if let Some(expiration_time) = &message.expiration_time {
+        if now > unix_timestamp(&expiration_time.to_string())? + skew {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn unix_timestamp(timestamp: &str) -> Result<i128, String> {
+    OffsetDateTime::parse(timestamp, &Rfc3339)
+        .map(|timestamp| i128::from(timestamp.unix_timestamp()))
+        .map_err(|e| format!("unable to parse timestamp from string: {}", e))
+}
+
+/// Verify the signature of a SIWE message and that it is valid at `now_unix_secs`,
+/// tolerating `skew_secs` (defaults to [`DEFAULT_SKEW_SECS`]) of clock skew.
+pub fn verify_siwe_message_at(
+    message: &str,
+    signature_hex: &str,
+    now_unix_secs: u64,
+    skew_secs: Option<u64>,
+) -> Result<bool, String> {
+    let parsed =
+        Message::from_str(message).map_err(|e| format!("unable to parse siwe message: {}", e))?;
+    if !siwe_time_valid(
+        &parsed,
+        now_unix_secs,
+        skew_secs.unwrap_or(DEFAULT_SKEW_SECS),
+    )? {
+        return Ok(false);
+    }
+    verify_siwe_signature(message, signature_hex)
+}
+
+/// Verify a signed SIWE message and its validity window at the given time in seconds
+/// since the Unix epoch (e.g. `Date.now() / 1000`).
+#[wasm_bindgen(js_name = verifySiweAt)]
+pub fn verify_siwe_at(
+    message: String,
+    signature_hex: String,
+    now_unix_secs: f64,
+    skew_secs: Option<u32>,
+) -> Result<bool, JsValue> {
+    Ok(verify_siwe_message_at(
+        &message,
+        &signature_hex,
+        now_unix_secs as u64,
+        skew_secs.map(u64::from),
+    )?)
+}
+
+/// Verify a compact JWS against a public JWK and return its payload.
+///
+/// The algorithm is taken from the JWS header and must match the key type, so both
+/// Ed25519 (`EdDSA`) and secp256k1 (`ES256K`) session keys are supported.
+pub fn verify_jws_payload(jws: &str, public_jwk_json: &str) -> Result<String, String> {
+    let key: JWK =
+        serde_json::from_str(public_jwk_json).map_err(|e| format!("invalid public JWK: {}", e))?;
+    let (_, payload) = jws::decode_verify(jws, &key).map_err(|e| format!("invalid JWS: {}", e))?;
+    String::from_utf8(payload).map_err(|e| format!("JWS payload is not valid UTF-8: {}", e))
+}
+
+/// Verify a compact JWS signed by a session key and return its payload.
+#[wasm_bindgen(js_name = verifyJws)]
+pub fn verify_jws(jws: String, public_jwk_json: String) -> Result<String, JsValue> {
+    Ok(verify_jws_payload(&jws, &public_jwk_json)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::session::manager::test::test_params;
+    use crate::session::{KeyAlgorithm, SessionManager};
+    use base64::Engine as _;
+
+    #[tokio::test]
+    async fn test_parse_built_message() {
+        let manager = SessionManager::new().unwrap();
+        let siwe = manager
+            .build_message(test_params(), None, None)
+            .unwrap()
+            .to_string();
+
+        let fields = siwe_fields(&siwe).unwrap();
+        assert_eq!(fields.address, "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed");
+        assert_eq!(fields.chain_id, 1);
+        assert_eq!(fields.domain, "example.com");
+        assert_eq!(fields.nonce, "abcdef1234567890");
+        assert_eq!(fields.uri, manager.get_did(None).unwrap());
+        assert!(fields
+            .resources
+            .contains(&"https://example.com/resource".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_parse_invalid_message() {
+        assert!(siwe_fields("not a siwe message").is_err());
+    }
+
+    #[cfg(feature = "nodejs")]
+    const SIGNER_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    #[cfg(feature = "nodejs")]
+    fn signed_message() -> (String, String) {
+        let manager = SessionManager::new().unwrap();
+        let mut params = test_params();
+        params.address = "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23".to_string();
+        let siwe = manager
+            .build_message(params, None, None)
+            .unwrap()
+            .to_string();
+        let signature =
+            crate::keys::sign_ethereum_message(siwe.clone(), SIGNER_KEY.to_string()).unwrap();
+        (siwe, signature)
+    }
+
+    #[cfg(feature = "nodejs")]
+    #[tokio::test]
+    async fn test_verify_siwe() {
+        let (siwe, signature) = signed_message();
+        assert_eq!(verify_siwe_signature(&siwe, &signature), Ok(true));
+    }
+
+    #[cfg(feature = "nodejs")]
+    #[tokio::test]
+    async fn test_verify_siwe_mutated_signature() {
+        let (siwe, signature) = signed_message();
+        let mut sig_bytes = hex::decode(signature).unwrap();
+        sig_bytes[10] ^= 0x01;
+        assert_eq!(
+            verify_siwe_signature(&siwe, &hex::encode(sig_bytes)),
+            Ok(false)
+        );
+    }
+
+    /// A message valid from 2024-01-01T00:00:00Z (1704067200) to 2024-01-01T01:00:00Z (1704070800).
+    fn windowed_message() -> Message {
+        let manager = SessionManager::new().unwrap();
+        let mut params = test_params();
+        params.not_before = Some("2024-01-01T00:00:00.000Z".to_string());
+        params.expiration_time = Some("2024-01-01T01:00:00.000Z".to_string());
+        manager.build_message(params, None, None).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_siwe_time_within_skew() {
+        let message = windowed_message();
+        // not before is 60s ahead of the verifier
+        assert_eq!(
+            siwe_time_valid(&message, 1704067140, DEFAULT_SKEW_SECS),
+            Ok(true)
+        );
+        // expired 60s ago for the verifier
+        assert_eq!(
+            siwe_time_valid(&message, 1704070860, DEFAULT_SKEW_SECS),
+            Ok(true)
+        );
+        assert_eq!(siwe_time_valid(&message, 1704068000, 0), Ok(true));
+    }
+
+    #[tokio::test]
+    async fn test_siwe_time_outside_skew() {
+        let message = windowed_message();
+        assert_eq!(
+            siwe_time_valid(&message, 1704067139, DEFAULT_SKEW_SECS),
+            Ok(false)
+        );
+        assert_eq!(
+            siwe_time_valid(&message, 1704070861, DEFAULT_SKEW_SECS),
+            Ok(false)
+        );
+        assert_eq!(siwe_time_valid(&message, 1704070801, 0), Ok(false));
+    }
+
+    #[cfg(feature = "nodejs")]
+    #[tokio::test]
+    async fn test_verify_siwe_at() {
+        let (siwe, signature) = signed_message();
+        assert_eq!(
+            verify_siwe_message_at(&siwe, &signature, 1704067200, None),
+            Ok(true)
+        );
+        let mut sig_bytes = hex::decode(&signature).unwrap();
+        sig_bytes[10] ^= 0x01;
+        assert_eq!(
+            verify_siwe_message_at(&siwe, &hex::encode(sig_bytes), 1704067200, None),
+            Ok(false)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verify_siwe_malformed_signature() {
+        let manager = SessionManager::new().unwrap();
+        let siwe = manager
+            .build_message(test_params(), None, None)
+            .unwrap()
+            .to_string();
+        assert!(verify_siwe_signature(&siwe, "0xnothex").is_err());
+        assert!(verify_siwe_signature(&siwe, "0x1234").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_jws() {
+        let mut manager = SessionManager::new().unwrap();
+        manager
+            .create_session_key_with_algorithm(
+                Some("secp256k1".to_string()),
+                KeyAlgorithm::Secp256k1,
+            )
+            .unwrap();
+        for key_id in [None, Some("secp256k1".to_string())] {
+            let jws = manager
+                .sign_jws("hello tinycloud".to_string(), key_id.clone())
+                .unwrap();
+            let public_jwk = manager.public_jwk(key_id).unwrap();
+            assert_eq!(
+                verify_jws_payload(&jws, &public_jwk),
+                Ok("hello tinycloud".to_string())
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_tampered_jws() {
+        let manager = SessionManager::new().unwrap();
+        let jws = manager
+            .sign_jws("hello tinycloud".to_string(), None)
+            .unwrap();
+        let public_jwk = manager.public_jwk(None).unwrap();
+
+        let segments: Vec<&str> = jws.split('.').collect();
+        let tampered = format!(
+            "{}.{}.{}",
+            segments[0],
+            base64::engine::general_purpose::URL_SAFE_NO_PAD.encode("goodbye tinycloud"),
+            segments[2]
+        );
+        assert!(verify_jws_payload(&tampered, &public_jwk).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_jws_wrong_key() {
+        let manager = SessionManager::new().unwrap();
+        let other = SessionManager::new().unwrap();
+        let jws = manager
+            .sign_jws("hello tinycloud".to_string(), None)
+            .unwrap();
+        assert!(verify_jws_payload(&jws, &other.public_jwk(None).unwrap()).is_err());
+    }
+}
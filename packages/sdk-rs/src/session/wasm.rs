@@ -1,7 +1,9 @@
 // use serde::{Deserialize, Serialize};
+use serde::Serialize;
 use serde_wasm_bindgen::to_value;
 
 use js_sys::JsString;
+use tinycloud_sdk_rs::tinycloud_auth::cacaos::siwe::generate_nonce;
 use wasm_bindgen::prelude::*;
 
 use super::manager;
@@ -27,12 +29,65 @@ impl TCWSessionManager {
         Ok(TCWSessionManager { manager })
     }
 
+    /// Initialize a TinyCloudWebSessionManager without any session keys, for
+    /// callers that import their own key.
+    pub fn empty() -> TCWSessionManager {
+        TCWSessionManager {
+            manager: manager::SessionManager::empty(),
+        }
+    }
+
     #[allow(non_snake_case)]
     /// Reset the SIWE message builder to its initial state.
     pub fn resetCapability(&mut self) {
         self.manager.reset_capability();
     }
 
+    #[allow(non_snake_case)]
+    /// Reset to a single new default session key and an empty capability.
+    pub fn resetAll(&mut self) -> Result<(), String> {
+        self.manager.reset_all()
+    }
+
+    #[allow(non_snake_case)]
+    /// Limit the number of actions the capability may hold.
+    pub fn setMaxAbilities(&mut self, max: usize) {
+        self.manager.set_max_abilities(max);
+    }
+
+    #[allow(non_snake_case)]
+    /// Check whether any actions have been added to the capability.
+    pub fn hasCapabilities(&self) -> bool {
+        self.manager.has_capabilities()
+    }
+
+    #[allow(non_snake_case)]
+    /// List the actions added to the capability, keyed by target.
+    pub fn listAbilities(&self) -> Result<JsValue, JsValue> {
+        let abilities = self.manager.list_abilities();
+        abilities
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(JsValue::from)
+    }
+
+    #[allow(non_snake_case)]
+    /// Remove every action whose target URI uses the given scheme (e.g. "kv").
+    pub fn removeNamespace(&mut self, namespace: String) -> bool {
+        self.manager.remove_namespace(&namespace)
+    }
+
+    #[allow(non_snake_case)]
+    /// Serialize the capability to JSON, for use with `importCapability`.
+    pub fn exportCapability(&self) -> Result<String, String> {
+        self.manager.export_capability()
+    }
+
+    #[allow(non_snake_case)]
+    /// Replace the capability with the output of `exportCapability`.
+    pub fn importCapability(&mut self, json: String) -> Result<(), String> {
+        self.manager.import_capability(json)
+    }
+
     /// Build a SIWE message for signing.
     pub fn build(
         self,
@@ -43,16 +98,98 @@ impl TCWSessionManager {
         self.manager.build(config, key_id, custom_uri)
     }
 
+    #[allow(non_snake_case)]
+    /// Build a SIWE message, returning `{ message, statement, uri, decodedRecap }`.
+    pub fn buildDetailed(
+        self,
+        config: SiweConfig,
+        key_id: Option<String>,
+        custom_uri: Option<String>,
+    ) -> Result<JsValue, JsValue> {
+        self.manager.build_detailed(config, key_id, custom_uri)
+    }
+
+    #[allow(non_snake_case)]
+    /// Build a SIWE message that expires `valid_for_secs` after `issuedAt`.
+    pub fn buildWithDuration(
+        self,
+        config: SiweConfig,
+        key_id: Option<String>,
+        custom_uri: Option<String>,
+        valid_for_secs: Option<u32>,
+    ) -> Result<String, JsValue> {
+        self.manager
+            .build_with_duration(config, key_id, custom_uri, valid_for_secs.map(u64::from))
+    }
+
     #[allow(non_snake_case)]
     /// Add actions for a specific target to a capability.
     pub fn addTargetedActions(&mut self, target: String, actions: Vec<JsString>) -> bool {
         self.manager.add_targeted_actions(target, actions)
     }
 
+    #[allow(non_snake_case)]
+    /// Add actions of a namespace for a specific target to a capability, attaching the
+    /// extra fields to each action as constraints.
+    pub fn addTargetedActionsWithFields(
+        &mut self,
+        namespace: String,
+        target: String,
+        actions: Vec<JsString>,
+        fields: ExtraFields,
+    ) -> bool {
+        self.manager
+            .add_targeted_actions_with_fields(namespace, target, actions, fields)
+    }
+
+    #[allow(non_snake_case)]
+    /// Add an action of a namespace for a specific target with its own validity window,
+    /// given in seconds since the Unix epoch.
+    pub fn addTargetedActionWithExpiry(
+        &mut self,
+        namespace: String,
+        target: String,
+        action: String,
+        not_before: Option<f64>,
+        expiration: Option<f64>,
+    ) -> bool {
+        self.manager.add_targeted_action_with_expiry(
+            namespace,
+            target,
+            action,
+            not_before.map(|secs| secs as u64),
+            expiration.map(|secs| secs as u64),
+        )
+    }
+
     #[allow(non_snake_case)]
     /// Create a new session key with the given key ID (Defaults to 'default').
-    pub fn createSessionKey(&mut self, key_id: Option<String>) -> Result<String, String> {
-        self.manager.create_session_key(key_id)
+    pub fn createSessionKey(&mut self, key_id: Option<String>) -> Result<String, JsValue> {
+        Ok(self.manager.create_session_key(key_id)?)
+    }
+
+    #[allow(non_snake_case)]
+    /// Create a new session key with the given algorithm ("Ed25519", "secp256k1" or "P-256").
+    pub fn createSessionKeyWithAlgorithm(
+        &mut self,
+        key_id: Option<String>,
+        algorithm: String,
+    ) -> Result<String, JsValue> {
+        let algorithm: manager::KeyAlgorithm = algorithm.parse()?;
+        Ok(self
+            .manager
+            .create_session_key_with_algorithm(key_id, algorithm)?)
+    }
+
+    #[allow(non_snake_case)]
+    /// Import an Ed25519 session key derived from a base64-encoded 32-byte seed.
+    pub fn importSessionKeyFromSeed(
+        &mut self,
+        seed_base64: String,
+        key_id: Option<String>,
+    ) -> Result<String, String> {
+        self.manager
+            .import_session_key_from_seed(seed_base64, key_id)
     }
 
     // #[allow(non_snake_case)]
@@ -76,14 +213,27 @@ impl TCWSessionManager {
         to_value(&keys).map_err(JsValue::from)
     }
 
+    #[allow(non_snake_case)]
+    /// Replace the session key key_id with a fresh key, keeping the capability.
+    pub fn rotateSessionKey(&mut self, key_id: Option<String>) -> Result<String, String> {
+        self.manager.rotate_session_key(key_id)
+    }
+
+    #[allow(non_snake_case)]
+    /// List the available session keys in the order they were created.
+    pub fn listSessionKeysSorted(&self) -> Result<JsValue, JsValue> {
+        let keys = self.manager.list_session_keys_sorted();
+        to_value(&keys).map_err(JsValue::from)
+    }
+
     #[allow(non_snake_case)]
     /// Rename the key_id to retrieve session data.
     pub fn renameSessionKeyId(
         &mut self,
         old_key_id: String,
         new_key_id: String,
-    ) -> Result<(), String> {
-        self.manager.rename_session_key_id(old_key_id, new_key_id)
+    ) -> Result<(), JsValue> {
+        Ok(self.manager.rename_session_key_id(old_key_id, new_key_id)?)
     }
 
     #[allow(non_snake_case)]
@@ -92,19 +242,138 @@ impl TCWSessionManager {
         self.manager.get_did(key_id)
     }
 
+    #[allow(non_snake_case)]
+    /// Get the algorithm of the session key key_id: "Ed25519", "secp256k1" or "P-256".
+    pub fn keyAlgorithm(&self, key_id: Option<String>) -> Result<String, String> {
+        self.manager.key_algorithm(key_id)
+    }
+
+    #[allow(non_snake_case)]
+    /// Get the compressed secp256k1 public key of the session key key_id as hex.
+    pub fn compressedPubkeyHex(&self, key_id: Option<String>) -> Result<String, String> {
+        self.manager.compressed_pubkey_hex(key_id)
+    }
+
+    #[allow(non_snake_case)]
+    /// Get the key ID, DID, public JWK, algorithm and session status of the session key key_id.
+    pub fn keySummary(&self, key_id: Option<String>) -> Result<JsValue, JsValue> {
+        self.manager.key_summary(key_id)
+    }
+
+    #[allow(non_snake_case)]
+    /// Sign a payload as a compact JWS with the session key key_id.
+    pub fn signJws(&self, payload: String, key_id: Option<String>) -> Result<String, String> {
+        self.manager.sign_jws(payload, key_id)
+    }
+
+    #[allow(non_snake_case)]
+    /// Select the DID method returned by `getDID`: "key" (the default) or
+    /// "pkh:eip155:<chain id>:<address>".
+    pub fn setDidMethod(&mut self, method: String) -> Result<(), String> {
+        self.manager.set_did_method(method.parse()?);
+        Ok(())
+    }
+
+    #[allow(non_snake_case)]
+    /// Get the DID verification method URI of the session key key_id.
+    pub fn getDidVm(&self, key_id: Option<String>) -> Result<String, String> {
+        self.manager.get_did_vm(key_id)
+    }
+
+    #[allow(non_snake_case)]
+    /// Get the did:pkh identity for an Ethereum address on the given chain.
+    pub fn getDidPkh(&self, chain_id: u32, address: String) -> Result<String, String> {
+        self.manager.get_did_pkh(chain_id as u64, &address)
+    }
+
     /// Get the full JWK associated with a the session key key_id.
     pub fn jwk(&self, key_id: Option<String>) -> Option<String> {
         self.manager.jwk(key_id)
     }
 
-    // #[allow(non_snake_case)]
-    // pub fn updateSession(
-    //     &mut self,
-    //     session: Session,
-    //     key_id: Option<String>,
-    // ) -> Result<(), String> {
-    //     self.manager.update_session(session, key_id)
-    // }
+    #[allow(non_snake_case)]
+    /// Get the public JWK associated with the session key key_id.
+    pub fn publicJwk(&self, key_id: Option<String>) -> Option<String> {
+        self.manager.public_jwk(key_id)
+    }
+
+    #[allow(non_snake_case)]
+    /// Drop all stored sessions while keeping the session keys.
+    pub fn clearSessions(&mut self) {
+        self.manager.clear_sessions();
+    }
+
+    #[allow(non_snake_case)]
+    /// Serialize all session keys, sessions and the capability to a JSON string.
+    pub fn exportState(&self) -> Result<String, String> {
+        self.manager.export_state()
+    }
+
+    /// Take an independent copy of the session keys, sessions and capability.
+    pub fn snapshot(&self) -> TCWSessionManager {
+        TCWSessionManager {
+            manager: self.manager.clone(),
+        }
+    }
+
+    /// Roll back to the state of a `snapshot`.
+    pub fn restore(&mut self, snapshot: &TCWSessionManager) {
+        self.manager = snapshot.manager.clone();
+    }
+
+    #[allow(non_snake_case)]
+    /// Restore a TCWSessionManager from the output of `exportState`.
+    pub fn importState(json: String) -> Result<TCWSessionManager, String> {
+        let manager = manager::SessionManager::import_state(json)?;
+        Ok(TCWSessionManager { manager })
+    }
+
+    #[allow(non_snake_case)]
+    /// Store the session returned by the TinyCloud node for the session key key_id
+    /// (defaults to the `kid` of the session JWK), along with the SIWE message it was
+    /// signed over. Without the SIWE message, `isSessionExpired` cannot tell when the
+    /// session expires.
+    pub fn updateSession(
+        &mut self,
+        js_session: JsValue,
+        key_id: Option<String>,
+        siwe: Option<String>,
+    ) -> Result<(), String> {
+        let session = serde_wasm_bindgen::from_value(js_session)
+            .map_err(|e| format!("Failed to parse session: {}", e))?;
+        self.manager.update_session_with_siwe(session, siwe, key_id)
+    }
+
+    #[allow(non_snake_case)]
+    /// Whether the session stored for key_id has expired, given the current
+    /// time in seconds since the Unix epoch (e.g. `Date.now() / 1000`).
+    pub fn isSessionExpired(
+        &self,
+        key_id: Option<String>,
+        now_unix_secs: f64,
+    ) -> Result<bool, String> {
+        self.manager
+            .is_session_expired(key_id, now_unix_secs as u64)
+    }
+}
+
+#[wasm_bindgen(js_name = supportedChainName)]
+/// Get a human-readable name for a well-known EIP-155 chain id.
+pub fn supported_chain_name_js(chain_id: u32) -> Option<String> {
+    manager::supported_chain_name(chain_id as u64)
+}
+
+#[wasm_bindgen(js_name = validateSiweConfig)]
+/// Check a SIWE config without building a message, returning every problem found.
+/// An empty array means the config is valid.
+pub fn validate_siwe_config(config: SiweConfig) -> Vec<String> {
+    manager::validate_config(&config).err().unwrap_or_default()
+}
+
+#[wasm_bindgen(js_name = generateNonce)]
+/// Generate a random alphanumeric SIWE nonce, as `build` does when no nonce is given.
+pub fn generate_nonce_js() -> String {
+    generate_nonce()
 }
 
 // Internal methods for key management (not exposed to wasm_bindgen directly)
@@ -119,6 +388,13 @@ impl TCWSessionManager {
     ) -> Result<String, String> {
         self.manager
             .import_session_key(key, key_id, override_existing)
+            .map_err(String::from)
+    }
+
+    /// Whether a session key exists (internal method for use by keys module)
+    #[cfg(feature = "nodejs")]
+    pub fn has_key(&self, key_id: &str) -> bool {
+        self.manager.has_session_key(key_id)
     }
 
     /// Get the JWK for a key ID (internal method for use by keys module)
@@ -131,3 +407,27 @@ impl TCWSessionManager {
         serde_json::from_str(&jwk_str).map_err(|e| format!("Failed to parse JWK: {}", e))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::session::manager::test::test_params;
+
+    #[tokio::test]
+    async fn test_generate_nonce() {
+        let nonce = generate_nonce_js();
+        assert!(nonce.len() >= 8);
+        assert!(nonce.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert_ne!(nonce, generate_nonce_js());
+    }
+
+    #[tokio::test]
+    async fn test_build_uses_generated_nonce() {
+        let manager = manager::SessionManager::new().unwrap();
+        let nonce = generate_nonce_js();
+        let mut params = test_params();
+        params.nonce = Some(nonce.clone());
+        let message = manager.build_message(params, None, None).unwrap();
+        assert_eq!(message.nonce, nonce);
+    }
+}